@@ -6,6 +6,10 @@ use clap::{Parser, Subcommand};
 use anyhow::{anyhow, Result};
 use std::path::Path;
 
+use ornament_core::modification::{
+    parse_sequence_fasta, parse_sequence_json, Domain, ModificationDatabase, ModifiedSequence,
+};
+
 #[derive(Parser)]
 #[command(name = "ornament")]
 #[command(author, version, about = "Modification-aware tRNA scanner", long_about = None)]
@@ -33,6 +37,14 @@ enum Commands {
         /// Output format (json, tsv)
         #[arg(short, long, default_value = "json")]
         format: String,
+
+        /// Treat input sequences as circular genomes (hits may span the origin)
+        #[arg(long)]
+        circular: bool,
+
+        /// Mitochondrial mode: circular genomes (use a mitochondrial tRNA model for --cm)
+        #[arg(long)]
+        mito: bool,
     },
 
     /// Analyze modification compatibility of tRNA sequences
@@ -41,29 +53,127 @@ enum Commands {
         #[arg(short, long)]
         input: String,
 
+        /// Covariance model to align FASTA input to (cmalign); derives
+        /// structure, Sprinzl numbering and anticodon
+        #[arg(long)]
+        cm: Option<String>,
+
+        /// Take isotype and anticodon from GtRNAdb-style FASTA names
+        /// (e.g. Homo_sapiens_tRNA-Ala-AGC-1-1) where they parse
+        #[arg(long)]
+        gtrnadb_names: bool,
+
         /// Output file (default: stdout)
         #[arg(short, long)]
         output: Option<String>,
 
-        /// Compatibility score threshold for "odd" tRNAs
-        #[arg(short, long, default_value = "0.8")]
-        threshold: f64,
+        /// Compatibility score threshold for "odd" tRNAs (overrides the
+        /// policy's score_threshold)
+        #[arg(short, long)]
+        threshold: Option<f64>,
+
+        /// Odd-calling policy file (TOML or JSON; default: built-in)
+        #[arg(long)]
+        policy: Option<String>,
+
+        /// Also write one row per (tRNA, Sprinzl position) to this file:
+        /// JSON Lines for .jsonl, TSV otherwise
+        #[arg(long)]
+        per_position: Option<String>,
+
+        /// Sequences are transcript-derived (tRNA-seq or nanopore consensus):
+        /// read a modification's sequencing signature as evidence of it
+        #[arg(long)]
+        transcript: bool,
+
+        /// Sequences are written in the MODOMICS alphabet (modified residues
+        /// as one-character codes, e.g. Ψ or P, D, ")
+        #[arg(long)]
+        modomics_alphabet: bool,
+
+        /// Database snapshot from `ornament db build` (replaces --modomics and --domain)
+        #[arg(long, conflicts_with_all = ["modomics", "domain"])]
+        db: Option<String>,
 
         /// MODOMICS JSON file for modification database (default: built-in)
         #[arg(long)]
         modomics: Option<String>,
+
+        /// Expectation set: eukaryotic, bacterial, archaeal, plastid,
+        /// mitochondrial, or an NCBI taxonomy ID
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Position expectation file (TOML or JSON) replacing the built-in table
+        #[arg(long)]
+        expectations: Option<String>,
+
+        /// Mitochondrial mode: armless-aware Sprinzl mapping, vertebrate mito
+        /// genetic code for isotype calls and mitochondrial expectations
+        #[arg(long)]
+        mito: bool,
+    },
+
+    /// Predict the modification sites lost when writer enzymes are knocked out
+    SimulateKnockout {
+        /// Input tRNA hits (JSON from scan)
+        #[arg(short, long)]
+        input: String,
+
+        /// Enzymes to knock out, by ID or gene name (e.g. TrmA, TRM2)
+        #[arg(short, long, required = true, value_delimiter = ',')]
+        enzyme: Vec<String>,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Output format (tsv, json); both are read by `compare --absent`
+        #[arg(short, long, default_value = "tsv")]
+        format: String,
+
+        /// Database snapshot from `ornament db build` (replaces --modomics and --domain)
+        #[arg(long, conflicts_with_all = ["modomics", "domain"])]
+        db: Option<String>,
+
+        /// MODOMICS JSON file for modification database (default: built-in)
+        #[arg(long)]
+        modomics: Option<String>,
+
+        /// Expectation set: eukaryotic, bacterial, archaeal, plastid,
+        /// mitochondrial, or an NCBI taxonomy ID
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Position expectation file (TOML or JSON) replacing the built-in table
+        #[arg(long)]
+        expectations: Option<String>,
+
+        /// Mitochondrial mode (as for analyze)
+        #[arg(long)]
+        mito: bool,
     },
 
     /// Compare with modkit modification calls
     Compare {
         /// tRNA analysis results (JSON)
-        #[arg(short, long)]
-        trna: String,
+        #[arg(short, long, required_unless_present = "absent")]
+        trna: Option<String>,
 
         /// modkit BedMethyl file
         #[arg(short, long)]
         modkit: String,
 
+        /// Predicted-absent sites from simulate-knockout (TSV or JSON), checked
+        /// against calls from the knockout strain
+        #[arg(long)]
+        absent: Option<String>,
+
+        /// Modified fraction (0-1) at which a predicted-absent site counts as
+        /// still modified
+        #[arg(long, default_value = "0.1")]
+        min_frequency: f64,
+
         /// Output file (default: stdout)
         #[arg(short, long)]
         output: Option<String>,
@@ -79,17 +189,380 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
 
+        /// Database snapshot from `ornament db build` (replaces --modomics and --domain)
+        #[arg(long, conflicts_with_all = ["modomics", "domain"])]
+        db: Option<String>,
+
+        /// MODOMICS JSON file for modification database (default: built-in)
+        #[arg(long)]
+        modomics: Option<String>,
+
+        /// Expectation set: eukaryotic, bacterial, archaeal, plastid,
+        /// mitochondrial, or an NCBI taxonomy ID
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Position expectation file (TOML or JSON) replacing the built-in table
+        #[arg(long)]
+        expectations: Option<String>,
+
+        /// Write the current position expectations to a file (.toml or .json)
+        #[arg(long)]
+        export_expectations: Option<String>,
+
+        /// List writer enzymes with their genes in the selected domain
+        #[arg(long)]
+        enzymes: bool,
+
+        /// Report how the MODOMICS snapshot was merged with the curated
+        /// catalogue: skipped entries, conflicts and (with --verbose) the
+        /// source of every field
+        #[arg(long)]
+        audit: bool,
+
+        /// Look up a modification by any code: short name, ChEBI ID, modkit
+        /// code or MODOMICS abbreviation
+        #[arg(long)]
+        resolve: Option<String>,
+    },
+
+    /// Build and inspect modification database snapshots
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Write a versioned, content-hashed database snapshot (JSON)
+    Build {
+        /// Snapshot file to write
+        #[arg(short, long)]
+        output: String,
+
         /// MODOMICS JSON file for modification database (default: built-in)
         #[arg(long)]
         modomics: Option<String>,
+
+        /// Expectation set: eukaryotic, bacterial, archaeal, plastid,
+        /// mitochondrial, or an NCBI taxonomy ID
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Position expectation file (TOML or JSON) replacing the built-in table
+        #[arg(long)]
+        expectations: Option<String>,
+    },
+
+    /// Fetch the MODOMICS modification and tRNA sequence endpoints into the
+    /// local cache (conditional requests; falls back to the cache offline)
+    Update {
+        /// MODOMICS server base URL
+        #[arg(long, default_value = ornament_core::integration::modomics_client::DEFAULT_BASE_URL)]
+        base_url: String,
+
+        /// Cache directory (default: $XDG_CACHE_HOME/ornament/modomics)
+        #[arg(long)]
+        cache_dir: Option<String>,
+
+        /// Do not contact the server; report the cached copies
+        #[arg(long)]
+        offline: bool,
+    },
+
+    /// List modifications observed in MODOMICS tRNA sequences, by organism,
+    /// isotype and Sprinzl position
+    Observed {
+        /// Sequence records: MODOMICS JSON (.json) or FASTA with
+        /// `>id|organism|isotype|anticodon` headers
+        input: String,
+
+        /// MODOMICS JSON file used to decode abbreviations (default: built-in)
+        #[arg(long)]
+        modomics: Option<String>,
+
+        /// Only this organism (e.g. "Saccharomyces cerevisiae")
+        #[arg(long)]
+        organism: Option<String>,
+
+        /// Only this isotype (e.g. "Phe")
+        #[arg(long)]
+        isotype: Option<String>,
+
+        /// Only this Sprinzl position (e.g. "37")
+        #[arg(short, long)]
+        position: Option<String>,
+
+        /// Output format (tsv, json)
+        #[arg(short, long, default_value = "tsv")]
+        format: String,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Learn a frequency-weighted expectation table from MODOMICS tRNA
+    /// sequences (load it with --expectations)
+    Learn {
+        /// Sequence records: MODOMICS JSON (.json) or FASTA with
+        /// `>id|organism|isotype|anticodon` headers
+        input: String,
+
+        /// Domain to learn: records are assigned by organism (all records
+        /// are used for mitochondrial and plastid)
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// MODOMICS JSON file used to decode abbreviations (default: built-in)
+        #[arg(long)]
+        modomics: Option<String>,
+
+        /// Reference tRNAs needed at a site before its frequency is used
+        #[arg(long, default_value = "3")]
+        min_records: usize,
+
+        /// Drop modifications seen in fewer than this fraction of tRNAs
+        #[arg(long, default_value = "0.05")]
+        min_frequency: f64,
+
+        /// Expectation file to write (.toml or .json; default: TOML to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
     },
+
+    /// Compare two MODOMICS releases and report what they change
+    Diff {
+        /// Older MODOMICS JSON file
+        old: String,
+
+        /// Newer MODOMICS JSON file
+        new: String,
+
+        /// tRNA hits (JSON from scan) to re-analyze against both releases
+        #[arg(short, long)]
+        input: Option<String>,
+
+        /// Expectation set: eukaryotic, bacterial, archaeal, plastid,
+        /// mitochondrial, or an NCBI taxonomy ID
+        #[arg(long)]
+        domain: Option<String>,
+
+        /// Position expectation file (TOML or JSON) replacing the built-in table
+        #[arg(long)]
+        expectations: Option<String>,
+
+        /// Mitochondrial mode (as for analyze)
+        #[arg(long)]
+        mito: bool,
+
+        /// Odd-calling policy file used when re-analyzing --input
+        #[arg(long)]
+        policy: Option<String>,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Output file (default: stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+/// Resolve the --domain argument (name or NCBI taxid), defaulting to eukaryotic
+fn parse_domain(domain: Option<&str>) -> Result<Domain> {
+    match domain {
+        Some(d) => d.parse::<Domain>().map_err(|e| anyhow!("{}", e)),
+        None => Ok(Domain::Eukaryotic),
+    }
+}
+
+/// Resolve --domain together with --mito, which implies mitochondrial expectations
+fn resolve_domain(domain: Option<&str>, mito: bool) -> Result<Domain> {
+    let domain = match (domain, mito) {
+        (None, true) => Domain::Mitochondrial,
+        (d, _) => parse_domain(d)?,
+    };
+    if mito && domain != Domain::Mitochondrial {
+        return Err(anyhow!("--mito cannot be combined with --domain {}", domain));
+    }
+    Ok(domain)
+}
+
+/// Analysis options for --mito and an optional --policy file
+fn analysis_options(mito: bool, policy: Option<&str>) -> Result<ornament_core::analysis::AnalysisOptions> {
+    use ornament_core::analysis::{AnalysisOptions, OddPolicy};
+
+    let options = if mito {
+        AnalysisOptions::mitochondrial()
+    } else {
+        AnalysisOptions::new()
+    };
+    match policy {
+        Some(path) => {
+            eprintln!("Loading odd policy from {}...", path);
+            let policy = OddPolicy::from_path(Path::new(path))
+                .map_err(|e| anyhow!("Failed to load odd policy: {}", e))?;
+            Ok(options.with_odd_policy(policy))
+        }
+        None => Ok(options),
+    }
+}
+
+/// Print how a modification shows up in transcript-derived sequences, if known
+fn print_signature(db: &ModificationDatabase, modification: &ornament_core::Modification) {
+    let Some(signature) = db.signature(&modification.short_name) else {
+        return;
+    };
+    let bases: Vec<String> = signature.mismatch_bases.iter().map(|b| b.to_string()).collect();
+    println!(
+        "      sequencing signature: reads as {}; RT stop {}, deletion {}",
+        bases.join("/"),
+        signature.rt_stop,
+        signature.deletion
+    );
+}
+
+/// Print a modification's MODOMICS ID and chemical properties, if known
+fn print_chemistry(modification: &ornament_core::Modification) {
+    if let Some(id) = modification.modomics_id {
+        println!("      MODOMICS {}", id);
+    }
+    if let Some(chebi) = modification.chebi_id {
+        println!("      ChEBI:{}", chebi);
+    }
+    let Some(chemistry) = &modification.chemistry else {
+        return;
+    };
+    if let Some(formula) = &chemistry.formula {
+        println!("      formula {}", formula);
+    }
+    let masses: Vec<String> = [
+        ("average", chemistry.mass_avg),
+        ("monoisotopic", chemistry.mass_monoiso),
+        ("[M+H]+", chemistry.mass_prot),
+    ]
+    .iter()
+    .filter_map(|(label, mass)| mass.map(|m| format!("{} {}", label, m)))
+    .collect();
+    if !masses.is_empty() {
+        println!("      mass {} Da", masses.join(", "));
+    }
+    if let Some(smiles) = &chemistry.smiles {
+        println!("      SMILES {}", smiles);
+    }
+    if !chemistry.product_ions.is_empty() {
+        let ions: Vec<String> = chemistry.product_ions.iter().map(|ion| ion.to_string()).collect();
+        println!("      product ions m/z {}", ions.join(", "));
+    }
+    match (&chemistry.lc_elution_time, &chemistry.lc_elution_comment) {
+        (Some(time), Some(comment)) => println!("      LC elution {}; {}", time, comment),
+        (Some(text), None) | (None, Some(text)) => println!("      LC elution {}", text),
+        (None, None) => {}
+    }
+}
+
+/// Print how the modification catalogue was assembled
+fn print_audit(db: &ModificationDatabase, verbose: bool) {
+    let Some(report) = db.merge_report() else {
+        println!(
+            "Built-in catalogue: {} curated modifications (pass --modomics to audit a snapshot merge)",
+            db.modifications().len()
+        );
+        return;
+    };
+
+    println!(
+        "Merged catalogue: {} curated entries matched in MODOMICS, {} curated only, {} MODOMICS only",
+        report.merged.len(),
+        report.curated_only.len(),
+        report.modomics_only.len()
+    );
+    if !report.curated_only.is_empty() {
+        println!("  curated only: {}", report.curated_only.join(", "));
+    }
+
+    println!("Conflicts ({}):", report.conflicts.len());
+    for conflict in &report.conflicts {
+        println!(
+            "  {} {}: curated '{}', MODOMICS '{}' (kept {})",
+            conflict.modification, conflict.field, conflict.curated, conflict.modomics, conflict.kept
+        );
+    }
+
+    println!("Skipped MODOMICS entries ({}):", report.skipped.len());
+    for entry in &report.skipped {
+        let label = if entry.short_name.is_empty() { &entry.name } else { &entry.short_name };
+        println!("  {} {} - {}", entry.id, label, entry.reason);
+    }
+
+    if verbose {
+        println!("Field sources:");
+        let mut names: Vec<&String> = db.modifications().keys().collect();
+        names.sort();
+        for name in names {
+            if let Some(provenance) = db.provenance(name) {
+                let fields: Vec<String> = provenance.fields()
+                    .map(|(field, source)| format!("{}={}", field, source))
+                    .collect();
+                println!("  {}: {}", name, fields.join(" "));
+            }
+        }
+    }
+}
+
+/// Hash stamped into a knockout table so `compare` can report the database
+const TABLE_HASH_PREFIX: &str = "#database_hash=";
+
+/// Load the modification database from a snapshot, or for a domain optionally
+/// from a MODOMICS file, with the position expectations replaced from an
+/// expectation file
+fn load_database(
+    snapshot: Option<&str>,
+    modomics: Option<&str>,
+    domain: Domain,
+    expectations: Option<&str>,
+) -> Result<ModificationDatabase> {
+    let mut db = if let Some(snapshot_path) = snapshot {
+        eprintln!("Loading database snapshot from {}...", snapshot_path);
+        ModificationDatabase::from_snapshot_file(Path::new(snapshot_path))
+            .map_err(|e| anyhow!("Failed to load database snapshot: {}", e))?
+    } else if let Some(modomics_path) = modomics {
+        eprintln!("Loading MODOMICS database from {}...", modomics_path);
+        ModificationDatabase::from_modomics_file_for_domain(Path::new(modomics_path), domain)
+            .map_err(|e| anyhow!("Failed to load MODOMICS file: {}", e))?
+    } else {
+        ModificationDatabase::for_domain(domain)
+    };
+
+    if let Some(expectations_path) = expectations {
+        eprintln!("Loading position expectations from {}...", expectations_path);
+        db.load_expectation_file(Path::new(expectations_path))
+            .map_err(|e| anyhow!("Failed to load expectation file: {}", e))?;
+    }
+
+    Ok(db)
+}
+
+/// Read MODOMICS sequence records from JSON (.json) or FASTA
+fn load_sequence_records(path: &str, db: &ModificationDatabase) -> Result<Vec<ModifiedSequence>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+    if path.ends_with(".json") {
+        parse_sequence_json(&content, db)
+    } else {
+        parse_sequence_fasta(&content, db)
+    }
+    .map_err(|e| anyhow!("Failed to parse {}: {}", path, e))
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Scan { input, cm, output, format } => {
+        Commands::Scan { input, cm, output, format, circular, mito } => {
             use ornament_core::infernal::InfernalRunner;
 
             let cm_path = cm.ok_or_else(|| anyhow!("--cm is required"))?;
@@ -109,7 +582,8 @@ fn main() -> Result<()> {
             // Run cmsearch subprocess
             let runner = InfernalRunner::new()
                 .with_cm(&cm_path)
-                .with_e_value(1e-5);
+                .with_e_value(1e-5)
+                .with_circular(circular || mito);
 
             let hits = runner.cmsearch(&input)?;
 
@@ -119,15 +593,16 @@ fn main() -> Result<()> {
             let output_str = match format.as_str() {
                 "json" => serde_json::to_string_pretty(&hits)?,
                 "tsv" => {
-                    let mut lines = vec!["target_name\tstart\tend\tstrand\tscore\te_value".to_string()];
+                    let mut lines = vec!["target_name\tstart\tend\tstrand\tscore\te_value\tspans_origin".to_string()];
                     for hit in &hits {
-                        lines.push(format!("{}\t{}\t{}\t{}\t{:.1}\t{:.2e}",
+                        lines.push(format!("{}\t{}\t{}\t{}\t{:.1}\t{:.2e}\t{}",
                             hit.target_name,
                             hit.target_start,
                             hit.target_end,
                             hit.strand,
                             hit.score,
-                            hit.e_value
+                            hit.e_value,
+                            hit.spans_origin
                         ));
                     }
                     lines.join("\n")
@@ -144,8 +619,12 @@ fn main() -> Result<()> {
             }
         }
 
-        Commands::Analyze { input, output, threshold, modomics } => {
-            use ornament_core::analysis::{TRNAHit, analyze_batch};
+        Commands::Analyze { input, cm, gtrnadb_names, output, threshold, policy, per_position, transcript, modomics_alphabet, db, modomics, domain, expectations, mito } => {
+            use ornament_core::analysis::{
+                GeneticCode, SequenceAlphabet, SequenceSource, SiteObservation, TRNAHit, analyze_batch_with_options,
+                hit_from_alignment, parse_gtrnadb_name,
+            };
+            use ornament_core::infernal::InfernalRunner;
 
             // Verify input file exists
             if !Path::new(&input).exists() {
@@ -153,32 +632,115 @@ fn main() -> Result<()> {
             }
 
             eprintln!("Analyzing modification compatibility in {}...", input);
-            eprintln!("Threshold: {}", threshold);
 
-            // Read input file (JSON from scan command)
+            // JSON from the scan command, or FASTA aligned to a covariance model
             let content = std::fs::read_to_string(&input)?;
-            let hits: Vec<TRNAHit> = serde_json::from_str(&content)
-                .map_err(|e| anyhow!("Failed to parse input JSON: {}. Expected output from 'ornament scan'.", e))?;
+            let is_json = match content.trim_start().chars().next() {
+                Some('[' | '{') => true,
+                Some('>') => false,
+                _ => return Err(anyhow!("Input {} is neither JSON from 'ornament scan' nor FASTA", input)),
+            };
+            let hits: Vec<TRNAHit> = if is_json {
+                serde_json::from_str(&content)
+                    .map_err(|e| anyhow!("Failed to parse input JSON: {}. Expected output from 'ornament scan'.", e))?
+            } else {
+                let cm_path = cm.as_deref().ok_or_else(|| anyhow!("--cm is required to align FASTA input"))?;
+                if !Path::new(cm_path).exists() {
+                    return Err(anyhow!("CM file not found: {}", cm_path));
+                }
+                eprintln!("Aligning {} to {}...", input, cm_path);
+                let aligned = InfernalRunner::new().with_cm(cm_path).cmalign(&input)?;
+                aligned.iter().map(|a| {
+                    let mut hit = hit_from_alignment(a);
+                    if let Some((isotype, anticodon)) = gtrnadb_names.then(|| parse_gtrnadb_name(&hit.id)).flatten() {
+                        hit.isotype = Some(isotype);
+                        hit.anticodon = Some(anticodon);
+                    }
+                    hit
+                }).collect()
+            };
 
             eprintln!("Loaded {} tRNA hits", hits.len());
 
-            // Load modification database
-            let db = if let Some(modomics_path) = modomics {
-                eprintln!("Loading MODOMICS database from {}...", modomics_path);
-                ornament_core::modification::ModificationDatabase::from_modomics_file(Path::new(&modomics_path))
-                    .map_err(|e| anyhow!("Failed to load MODOMICS file: {}", e))?
-            } else {
-                ornament_core::modification::ModificationDatabase::eukaryotic()
-            };
-            let results = analyze_batch(&hits, &db);
+            // Load modification database (--mito implies mitochondrial expectations)
+            let domain = resolve_domain(domain.as_deref(), mito)?;
+            let db = load_database(db.as_deref(), modomics.as_deref(), domain, expectations.as_deref())?;
+            let database_hash = db.content_hash();
+            eprintln!("Expectation set: {}", db.domain());
+            eprintln!("Database: {}", database_hash);
+            let mut options = analysis_options(mito, policy.as_deref())?;
+            if let Some(threshold) = threshold {
+                let policy = options.odd_policy().clone().with_score_threshold(threshold);
+                options = options.with_odd_policy(policy);
+            }
+            if !is_json {
+                // Aligned hits carry no isotype unless named; call it from the anticodon
+                options = options.with_structure_aware_mapping(true);
+                if !mito {
+                    options = options.with_genetic_code(GeneticCode::Standard);
+                }
+            }
+            if transcript {
+                options = options.with_sequence_source(SequenceSource::Transcript);
+            }
+            if modomics_alphabet {
+                options = options.with_alphabet(SequenceAlphabet::Modomics);
+            }
+            let policy = options.odd_policy().clone();
+            eprintln!("Odd policy: score < {}, severity {:?} or worse", policy.score_threshold, policy.min_severity);
+            let results = analyze_batch_with_options(&hits, &db, &options);
 
-            // Filter to odd tRNAs based on threshold
-            let odd_results: Vec<_> = results.results.iter()
-                .filter(|r| r.compatibility_score < threshold)
+            // Odd tRNAs as called by the policy, most anomalous first
+            let odd_results: Vec<_> = results.ranked().into_iter()
+                .filter(|r| r.is_odd)
                 .collect();
 
-            eprintln!("Found {} odd tRNAs (score < {})", odd_results.len(), threshold);
+            eprintln!("Found {} odd tRNAs", odd_results.len());
             eprintln!("Average compatibility: {:.2}%", results.average_compatibility * 100.0);
+            eprintln!("Average log-likelihood: {:.3}", results.average_log_likelihood);
+            let masked: Vec<_> = results.results.iter().filter(|r| r.not_evaluable > 0).collect();
+            if !masked.is_empty() {
+                eprintln!(
+                    "Warning: {} expected sites in {} tRNAs could not be evaluated (N or non-IUPAC bases)",
+                    masked.iter().map(|r| r.not_evaluable).sum::<usize>(),
+                    masked.len()
+                );
+            }
+            if transcript {
+                let signed = results.results.iter()
+                    .flat_map(|r| &r.verdicts)
+                    .filter(|v| v.signature.is_some())
+                    .count();
+                eprintln!("Read {} sites as modifications from their sequencing signature", signed);
+            }
+            if modomics_alphabet {
+                let observed: Vec<&SiteObservation> = results.results.iter()
+                    .flat_map(|r| &r.verdicts)
+                    .filter_map(|v| v.observed.as_ref())
+                    .collect();
+                let count = |f: fn(&SiteObservation) -> bool| observed.iter().filter(|o| f(o)).count();
+                eprintln!(
+                    "Expected sites: {} carry the expected modification, {} another one, {} none",
+                    count(|o| matches!(o, SiteObservation::Expected { .. })),
+                    count(|o| matches!(o, SiteObservation::Different { .. })),
+                    count(|o| matches!(o, SiteObservation::Unmodified))
+                );
+            }
+            for loss in &results.enzyme_losses {
+                eprintln!("  {}: substrate site lost in {} tRNAs", loss.enzyme, loss.trnas);
+            }
+
+            if let Some(path) = per_position {
+                use ornament_core::output::{to_per_position_jsonl, to_per_position_tsv};
+
+                let table = if path.ends_with(".jsonl") {
+                    to_per_position_jsonl(&results.results)?
+                } else {
+                    to_per_position_tsv(&results.results)
+                };
+                std::fs::write(&path, table)?;
+                eprintln!("Per-position table written to {}", path);
+            }
 
             // Format output
             let output_data = serde_json::json!({
@@ -186,7 +748,13 @@ fn main() -> Result<()> {
                     "total_trnas": results.total_trnas,
                     "odd_trnas": results.odd_trnas,
                     "average_compatibility": results.average_compatibility,
-                    "threshold": threshold
+                    "average_log_likelihood": results.average_log_likelihood,
+                    "threshold": policy.score_threshold,
+                    "odd_policy": policy,
+                    "sequence_source": options.sequence_source(),
+                    "alphabet": options.alphabet(),
+                    "enzyme_losses": results.enzyme_losses,
+                    "database_hash": database_hash
                 },
                 "odd_trnas": odd_results.iter().map(|r| {
                     serde_json::json!({
@@ -197,12 +765,20 @@ fn main() -> Result<()> {
                         "isotype": r.hit.isotype,
                         "anticodon": r.hit.anticodon,
                         "compatibility_score": r.compatibility_score,
+                        "log_likelihood": r.log_likelihood,
+                        "contributions": r.contributions,
+                        "not_evaluable": r.not_evaluable,
                         "incompatibilities": r.incompatibilities.iter().map(|i| {
                             serde_json::json!({
                                 "position": i.position.0,
                                 "observed_base": i.observed_base.to_char(),
                                 "expected_modification": i.expected_mod_name,
-                                "severity": format!("{:?}", i.severity)
+                                "severity": format!("{:?}", i.severity),
+                                "kind": format!("{:?}", i.kind),
+                                "lost_context": i.lost_context.as_ref().map(|p| p.to_string()),
+                                "prerequisite": i.prerequisite.as_ref().map(|p| p.to_string()),
+                                "enzymes": i.enzymes,
+                                "evidence": i.evidence
                             })
                         }).collect::<Vec<_>>()
                     })
@@ -221,94 +797,194 @@ fn main() -> Result<()> {
             }
         }
 
-        Commands::Compare { trna, modkit, output } => {
-            use ornament_core::analysis::ModCompatibilityResult;
-            use ornament_core::integration::modkit::parse_bedmethyl;
+        Commands::SimulateKnockout { input, enzyme, output, format, db, modomics, domain, expectations, mito } => {
+            use ornament_core::analysis::{TRNAHit, simulate_knockout};
+            use ornament_core::analysis::knockout::write_absence_table;
 
-            // Verify input files exist
-            if !Path::new(&trna).exists() {
-                return Err(anyhow!("tRNA file not found: {}", trna));
-            }
-            if !Path::new(&modkit).exists() {
-                return Err(anyhow!("modkit file not found: {}", modkit));
+            if !Path::new(&input).exists() {
+                return Err(anyhow!("Input file not found: {}", input));
             }
 
-            eprintln!("Comparing {} with modkit calls from {}...", trna, modkit);
+            let content = std::fs::read_to_string(&input)?;
+            let hits: Vec<TRNAHit> = serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Failed to parse input JSON: {}. Expected output from 'ornament scan'.", e))?;
+
+            let domain = resolve_domain(domain.as_deref(), mito)?;
+            let db = load_database(db.as_deref(), modomics.as_deref(), domain, expectations.as_deref())?;
+            let knocked_out = enzyme.iter()
+                .map(|name| db.find_enzyme(name).ok_or_else(|| {
+                    anyhow!("Unknown enzyme: {}. See 'ornament mods --enzymes'", name)
+                }))
+                .collect::<Result<Vec<_>>>()?;
 
-            // Load tRNA analysis results
-            let trna_content = std::fs::read_to_string(&trna)?;
-            let trna_data: serde_json::Value = serde_json::from_str(&trna_content)?;
+            let names: Vec<&str> = knocked_out.iter().map(|e| e.id.as_str()).collect();
+            eprintln!("Simulating knockout of {} in {} tRNA hits...", names.join(", "), hits.len());
 
-            // Extract results from analysis output
-            let trna_results: Vec<ModCompatibilityResult> = if let Some(results) = trna_data.get("all_results") {
-                serde_json::from_value(results.clone())?
-            } else {
-                // Try parsing as direct array of results
-                serde_json::from_str(&trna_content)?
+            let options = analysis_options(mito, None)?;
+            let absences = simulate_knockout(&hits, &db, &options, &knocked_out);
+
+            let direct = absences.iter().filter(|a| a.prerequisite.is_none()).count();
+            eprintln!(
+                "Predicted {} absent sites ({} direct, {} via lost prerequisites)",
+                absences.len(),
+                direct,
+                absences.len() - direct
+            );
+
+            let output_str = match format.as_str() {
+                "tsv" => format!("{}{}\n{}", TABLE_HASH_PREFIX, db.content_hash(), write_absence_table(&absences)),
+                "json" => serde_json::to_string_pretty(&serde_json::json!({
+                    "database_hash": db.content_hash(),
+                    "absences": absences,
+                }))?,
+                _ => return Err(anyhow!("Unknown format: {}. Use 'tsv' or 'json'", format)),
             };
 
-            eprintln!("Loaded {} tRNA results", trna_results.len());
+            if let Some(output_path) = output {
+                std::fs::write(&output_path, &output_str)?;
+                eprintln!("Results written to {}", output_path);
+            } else {
+                print!("{}", output_str);
+            }
+        }
+
+        Commands::Compare { trna, modkit, absent, min_frequency, output } => {
+            use ornament_core::analysis::{AbsenceCheck, AbsenceStatus, ModCompatibilityResult, PredictedAbsence};
+            use ornament_core::analysis::knockout::parse_absence_table;
+            use ornament_core::integration::modkit::parse_bedmethyl;
+
+            // Verify input files exist
+            for path in trna.iter().chain(absent.iter()).chain(std::iter::once(&modkit)) {
+                if !Path::new(path).exists() {
+                    return Err(anyhow!("File not found: {}", path));
+                }
+            }
 
             // Load modkit BedMethyl data
             let modkit_content = std::fs::read_to_string(&modkit)?;
             let modkit_records = parse_bedmethyl(&modkit_content);
 
-            eprintln!("Loaded {} modkit modification calls", modkit_records.len());
+            eprintln!("Loaded {} modkit modification calls from {}", modkit_records.len(), modkit);
 
-            // Build comparison: find modkit calls that overlap with tRNA positions
-            let mut comparisons = Vec::new();
+            let mut summary = serde_json::json!({
+                "total_modkit_records": modkit_records.len()
+            });
+            let mut output_data = serde_json::json!({});
+            let mut database_hashes: Vec<String> = Vec::new();
 
-            for trna_result in &trna_results {
-                let hit = &trna_result.hit;
+            if let Some(trna) = trna {
+                eprintln!("Comparing {} with modkit calls...", trna);
 
-                // Find modkit records overlapping this tRNA
-                let overlapping: Vec<_> = modkit_records.iter()
-                    .filter(|r| {
-                        r.chrom == hit.seq_name &&
-                        r.start >= hit.start &&
-                        r.end <= hit.end
-                    })
-                    .collect();
+                // Load tRNA analysis results
+                let trna_content = std::fs::read_to_string(&trna)?;
+                let trna_data: serde_json::Value = serde_json::from_str(&trna_content)?;
+                if let Some(hash) = trna_data.pointer("/summary/database_hash").and_then(|h| h.as_str()) {
+                    database_hashes.push(hash.to_string());
+                }
 
-                if !overlapping.is_empty() {
-                    comparisons.push(serde_json::json!({
-                        "trna_id": hit.id,
-                        "seq_name": hit.seq_name,
-                        "start": hit.start,
-                        "end": hit.end,
-                        "isotype": hit.isotype,
-                        "compatibility_score": trna_result.compatibility_score,
-                        "is_odd": trna_result.is_odd,
-                        "modkit_calls": overlapping.iter().map(|r| {
-                            serde_json::json!({
-                                "position": r.start,
-                                "mod_code": r.mod_code,
-                                "strand": r.strand.to_string(),
-                                "coverage": r.coverage,
-                                "mod_frequency": r.mod_frequency
-                            })
-                        }).collect::<Vec<_>>(),
-                        "expected_incompatibilities": trna_result.incompatibilities.iter().map(|i| {
-                            serde_json::json!({
-                                "position": i.position.0,
-                                "expected_mod": i.expected_mod_name
-                            })
-                        }).collect::<Vec<_>>()
-                    }));
+                // Extract results from analysis output
+                let trna_results: Vec<ModCompatibilityResult> = if let Some(results) = trna_data.get("all_results") {
+                    serde_json::from_value(results.clone())?
+                } else {
+                    // Try parsing as direct array of results
+                    serde_json::from_str(&trna_content)?
+                };
+
+                eprintln!("Loaded {} tRNA results", trna_results.len());
+
+                // Build comparison: find modkit calls that overlap with tRNA positions
+                let mut comparisons = Vec::new();
+
+                for trna_result in &trna_results {
+                    let hit = &trna_result.hit;
+
+                    // Find modkit records overlapping this tRNA
+                    let overlapping: Vec<_> = modkit_records.iter()
+                        .filter(|r| {
+                            r.chrom == hit.seq_name &&
+                            r.start >= hit.start &&
+                            r.end <= hit.end
+                        })
+                        .collect();
+
+                    if !overlapping.is_empty() {
+                        comparisons.push(serde_json::json!({
+                            "trna_id": hit.id,
+                            "seq_name": hit.seq_name,
+                            "start": hit.start,
+                            "end": hit.end,
+                            "isotype": hit.isotype,
+                            "compatibility_score": trna_result.compatibility_score,
+                            "log_likelihood": trna_result.log_likelihood,
+                            "is_odd": trna_result.is_odd,
+                            "modkit_calls": overlapping.iter().map(|r| {
+                                serde_json::json!({
+                                    "position": r.start,
+                                    "mod_code": r.mod_code,
+                                    "strand": r.strand.to_string(),
+                                    "coverage": r.coverage,
+                                    "mod_frequency": r.mod_frequency
+                                })
+                            }).collect::<Vec<_>>(),
+                            "expected_incompatibilities": trna_result.incompatibilities.iter().map(|i| {
+                                serde_json::json!({
+                                    "position": i.position.0,
+                                    "expected_mod": i.expected_mod_name
+                                })
+                            }).collect::<Vec<_>>()
+                        }));
+                    }
                 }
+
+                eprintln!("Found {} tRNAs with modkit overlaps", comparisons.len());
+
+                summary["total_trnas"] = trna_results.len().into();
+                summary["trnas_with_modkit_calls"] = comparisons.len().into();
+                output_data["comparisons"] = comparisons.into();
             }
 
-            eprintln!("Found {} tRNAs with modkit overlaps", comparisons.len());
+            if let Some(absent) = absent {
+                let table = std::fs::read_to_string(&absent)?;
+                let absences: Vec<PredictedAbsence> = if table.trim_start().starts_with('{') {
+                    let data: serde_json::Value = serde_json::from_str(&table)
+                        .map_err(|e| anyhow!("Failed to parse {}: {}", absent, e))?;
+                    if let Some(hash) = data.get("database_hash").and_then(|h| h.as_str()) {
+                        database_hashes.push(hash.to_string());
+                    }
+                    serde_json::from_value(data.get("absences").cloned().unwrap_or_default())
+                        .map_err(|e| anyhow!("Failed to parse {}: {}", absent, e))?
+                } else {
+                    database_hashes.extend(table.lines().filter_map(|l| l.strip_prefix(TABLE_HASH_PREFIX)).map(str::to_string));
+                    parse_absence_table(&table)
+                        .map_err(|e| anyhow!("Failed to parse {}: {}", absent, e))?
+                };
+                let checks = AbsenceCheck::check_all(&absences, &modkit_records, min_frequency);
 
-            let output_data = serde_json::json!({
-                "summary": {
-                    "total_trnas": trna_results.len(),
-                    "trnas_with_modkit_calls": comparisons.len(),
-                    "total_modkit_records": modkit_records.len()
-                },
-                "comparisons": comparisons
-            });
+                let count = |status| checks.iter().filter(|c| c.status == status).count();
+                let (confirmed, unexpected, uncovered) = (
+                    count(AbsenceStatus::Confirmed),
+                    count(AbsenceStatus::Unexpected),
+                    count(AbsenceStatus::NoCoverage),
+                );
+                eprintln!(
+                    "Predicted-absent sites: {} confirmed, {} still modified, {} without coverage",
+                    confirmed, unexpected, uncovered
+                );
 
+                summary["predicted_absent_sites"] = checks.len().into();
+                summary["absence_confirmed"] = confirmed.into();
+                summary["absence_unexpected"] = unexpected.into();
+                summary["absence_no_coverage"] = uncovered.into();
+                output_data["absence_checks"] = serde_json::to_value(&checks)?;
+            }
+
+            database_hashes.sort();
+            database_hashes.dedup();
+            if database_hashes.len() > 1 {
+                eprintln!("Warning: inputs were produced with different databases: {}", database_hashes.join(", "));
+            }
+            summary["database_hash"] = database_hashes.first().cloned().into();
+            output_data["summary"] = summary;
             let output_str = serde_json::to_string_pretty(&output_data)?;
 
             if let Some(output_path) = output {
@@ -319,14 +995,54 @@ fn main() -> Result<()> {
             }
         }
 
-        Commands::Mods { position, verbose, modomics } => {
-            let db = if let Some(modomics_path) = modomics {
-                eprintln!("Loading MODOMICS database from {}...", modomics_path);
-                ornament_core::modification::ModificationDatabase::from_modomics_file(Path::new(&modomics_path))
-                    .map_err(|e| anyhow!("Failed to load MODOMICS file: {}", e))?
-            } else {
-                ornament_core::modification::ModificationDatabase::eukaryotic()
-            };
+        Commands::Mods { position, verbose, db, modomics, domain, expectations, export_expectations, enzymes, audit, resolve } => {
+            let domain = parse_domain(domain.as_deref())?;
+            let db = load_database(db.as_deref(), modomics.as_deref(), domain, expectations.as_deref())?;
+            let domain = db.domain();
+
+            if let Some(export_path) = export_expectations {
+                db.export_expectations(Some(domain.name()))
+                    .write(Path::new(&export_path))
+                    .map_err(|e| anyhow!("Failed to write expectation file: {}", e))?;
+                eprintln!(
+                    "Wrote {} position expectations to {}",
+                    db.expectations().len(),
+                    export_path
+                );
+                return Ok(());
+            }
+
+            if audit {
+                print_audit(&db, verbose);
+                return Ok(());
+            }
+
+            if let Some(code) = resolve {
+                let modification = db.resolve(&code).map_err(|e| anyhow!("{}", e))?;
+                println!("{} -> {} ({})", code, modification.short_name, modification.name);
+                if verbose {
+                    print_chemistry(modification);
+                }
+                return Ok(());
+            }
+
+            if enzymes {
+                println!("Writer enzymes ({} genes):", domain);
+                for enzyme in db.enzymes() {
+                    let genes = enzyme.genes_for_domain(domain);
+                    let genes = if genes.is_empty() { "-".to_string() } else { genes.join(", ") };
+                    println!("  {} [{}] - {}", enzyme.id, genes, enzyme.modifications.join(", "));
+                    if verbose {
+                        if let Some(description) = &enzyme.description {
+                            println!("      {}", description);
+                        }
+                        for determinant in &enzyme.determinants {
+                            println!("      recognizes {}", determinant);
+                        }
+                    }
+                }
+                return Ok(());
+            }
 
             if let Some(pos) = position {
                 let sprinzl = ornament_core::SprinzlPosition(pos.clone());
@@ -334,12 +1050,43 @@ fn main() -> Result<()> {
                 if !expectations.is_empty() {
                     println!("Modifications expected at position {}:", pos);
                     for exp in expectations {
+                        if let Some(editing) = exp.editing.filter(|_| exp.modifications.is_empty()) {
+                            println!("  {} editing", editing);
+                        }
                         for modification in &exp.modifications {
                             if verbose {
                                 println!("  {} ({}) - {:?} conservation",
                                          modification.name,
                                          modification.short_name,
                                          exp.conservation);
+                                if let Some(editing) = exp.editing {
+                                    println!("      edited: genomic {}, read as {} once edited", editing.from, editing.to);
+                                }
+                                for predicate in &exp.applies_when {
+                                    println!("      applies when {}", predicate);
+                                }
+                                for predicate in &exp.requires {
+                                    println!("      requires {}", predicate);
+                                }
+                                for prerequisite in &exp.depends_on {
+                                    println!("      depends on {}", prerequisite);
+                                }
+                                for enzyme in db.enzymes_for(exp, modification) {
+                                    let genes = enzyme.genes_for_domain(domain);
+                                    if genes.is_empty() {
+                                        println!("      enzyme {}", enzyme.id);
+                                    } else {
+                                        println!("      enzyme {} ({})", enzyme.id, genes.join(", "));
+                                    }
+                                }
+                                if let Some(evidence) = &exp.evidence {
+                                    println!("      evidence: {}", evidence);
+                                }
+                                if let Some(frequency) = exp.frequency {
+                                    println!("      observed frequency: {:.1}%", frequency * 100.0);
+                                }
+                                print_chemistry(modification);
+                                print_signature(&db, modification);
                             } else {
                                 println!("  {}", modification.short_name);
                             }
@@ -357,12 +1104,235 @@ fn main() -> Result<()> {
                                  modification.short_name,
                                  modification.parent_base,
                                  modification.genomic_expectation);
+                        print_chemistry(modification);
+                        print_signature(&db, modification);
                     } else {
                         println!("  {} ({})", modification.short_name, name);
                     }
                 }
             }
         }
+
+        Commands::Db { command } => match command {
+            DbCommand::Build { output, modomics, domain, expectations } => {
+                let domain = parse_domain(domain.as_deref())?;
+                let db = load_database(None, modomics.as_deref(), domain, expectations.as_deref())?;
+                let snapshot = db.to_snapshot();
+                snapshot.write(Path::new(&output))
+                    .map_err(|e| anyhow!("Failed to write database snapshot: {}", e))?;
+                eprintln!(
+                    "Wrote {} modifications, {} enzymes and {} position expectations to {}",
+                    snapshot.contents.modifications.len(),
+                    snapshot.contents.enzymes.len(),
+                    snapshot.contents.expectations.len(),
+                    output
+                );
+                println!("{}", snapshot.content_hash);
+            }
+
+            DbCommand::Update { base_url, cache_dir, offline } => {
+                use ornament_core::integration::modomics_client::{default_cache_dir, Endpoint, ModomicsClient};
+
+                let cache_dir = cache_dir.map(std::path::PathBuf::from).unwrap_or_else(default_cache_dir);
+                let client = ModomicsClient::new(&cache_dir)
+                    .with_base_url(&base_url)
+                    .with_offline(offline);
+
+                let mut failed = 0;
+                for endpoint in Endpoint::defaults() {
+                    match client.update(&endpoint) {
+                        Ok(outcome) => {
+                            if let Some(warning) = &outcome.warning {
+                                eprintln!("Warning: {}; using cached copy", warning);
+                            }
+                            println!(
+                                "{}\t{}\t{}\tfetched {}\tetag {}",
+                                outcome.endpoint,
+                                outcome.status,
+                                outcome.path.display(),
+                                outcome.meta.fetched_at,
+                                outcome.meta.etag.as_deref().unwrap_or("-")
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("{}: {}", endpoint.name, e);
+                            failed += 1;
+                        }
+                    }
+                }
+                if failed > 0 {
+                    return Err(anyhow!("{} endpoint(s) could not be updated", failed));
+                }
+            }
+
+            DbCommand::Observed { input, modomics, organism, isotype, position, format, output } => {
+                use ornament_core::modification::ObservedModifications;
+
+                let db = load_database(None, modomics.as_deref(), Domain::Eukaryotic, None)?;
+                let records = load_sequence_records(&input, &db)?;
+
+                let observed = ObservedModifications::from_records(&records);
+                eprintln!(
+                    "{} records, {} observed modification sites in {} organisms ({} records could not be numbered)",
+                    records.len(),
+                    observed.len(),
+                    observed.organisms().len(),
+                    observed.unaligned.len()
+                );
+                if !observed.unknown_codes.is_empty() {
+                    let codes: Vec<String> = observed.unknown_codes.iter().map(|(c, n)| format!("{} ({})", c, n)).collect();
+                    eprintln!("Unresolved codes: {}", codes.join(", "));
+                }
+
+                let position = position.map(ornament_core::SprinzlPosition);
+                let facts = observed.query(organism.as_deref(), isotype.as_deref(), position.as_ref());
+                let output_str = match format.as_str() {
+                    "tsv" => {
+                        let mut lines = vec!["organism\tisotype\tposition\tmodification".to_string()];
+                        for f in &facts {
+                            lines.push(format!(
+                                "{}\t{}\t{}\t{}",
+                                f.organism,
+                                f.isotype.as_deref().unwrap_or("."),
+                                f.position,
+                                f.modification
+                            ));
+                        }
+                        lines.join("\n")
+                    }
+                    "json" => serde_json::to_string_pretty(&facts)?,
+                    _ => return Err(anyhow!("Unknown format: {}. Use 'tsv' or 'json'", format)),
+                };
+
+                if let Some(output_path) = output {
+                    std::fs::write(&output_path, &output_str)?;
+                    eprintln!("Results written to {}", output_path);
+                } else {
+                    println!("{}", output_str);
+                }
+            }
+
+            DbCommand::Learn { input, domain, modomics, min_records, min_frequency, output } => {
+                use ornament_core::modification::{learn_expectations, LearnOptions};
+
+                let domain = parse_domain(domain.as_deref())?;
+                let db = load_database(None, modomics.as_deref(), domain, None)?;
+                let records = load_sequence_records(&input, &db)?;
+                let options = LearnOptions::new()
+                    .with_min_records(min_records)
+                    .with_min_frequency(min_frequency);
+                let learned = learn_expectations(&records, domain, &options);
+
+                eprintln!(
+                    "Learned {} {} expectations from {} of {} records ({} other domain, {} unplaced organism, {} not numbered)",
+                    learned.file.expectations.len(),
+                    domain,
+                    learned.records_used,
+                    records.len(),
+                    learned.other_domain.len(),
+                    learned.unplaced.len(),
+                    learned.unaligned.len()
+                );
+
+                if let Some(output_path) = output {
+                    learned.file.write(Path::new(&output_path))
+                        .map_err(|e| anyhow!("Failed to write expectation file: {}", e))?;
+                    eprintln!("Results written to {}", output_path);
+                } else {
+                    let toml = learned.file.to_toml_string()
+                        .map_err(|e| anyhow!("Failed to serialize expectation table: {}", e))?;
+                    print!("{}", toml);
+                }
+            }
+
+            DbCommand::Diff { old, new, input, domain, expectations, mito, policy, format, output } => {
+                use ornament_core::analysis::{TRNAHit, analyze_batch_with_options};
+                use ornament_core::modification::{diff_modifications, odd_status_changes, parse_modomics_file};
+
+                let parse = |path: &str| parse_modomics_file(Path::new(path))
+                    .map_err(|e| anyhow!("Failed to load MODOMICS file {}: {}", path, e));
+                let diff = diff_modifications(&parse(&old)?, &parse(&new)?);
+
+                let domain = resolve_domain(domain.as_deref(), mito)?;
+                let db = load_database(None, None, domain, expectations.as_deref())?;
+                let affected = diff.affected_expectations(&db);
+
+                let odd_changes = match input {
+                    Some(input) => {
+                        let content = std::fs::read_to_string(&input)?;
+                        let hits: Vec<TRNAHit> = serde_json::from_str(&content)
+                            .map_err(|e| anyhow!("Failed to parse input JSON: {}. Expected output from 'ornament scan'.", e))?;
+                        let options = analysis_options(mito, policy.as_deref())?;
+                        let old_db = load_database(None, Some(&old), domain, expectations.as_deref())?;
+                        let new_db = load_database(None, Some(&new), domain, expectations.as_deref())?;
+                        let old_results = analyze_batch_with_options(&hits, &old_db, &options);
+                        let new_results = analyze_batch_with_options(&hits, &new_db, &options);
+                        eprintln!("Re-analyzed {} tRNA hits against both releases", hits.len());
+                        Some(odd_status_changes(&old_results.results, &new_results.results))
+                    }
+                    None => None,
+                };
+
+                let output_str = match format.as_str() {
+                    "json" => serde_json::to_string_pretty(&serde_json::json!({
+                        "added": diff.added,
+                        "removed": diff.removed,
+                        "changed": diff.changed,
+                        "affected_expectations": affected,
+                        "odd_status_changes": odd_changes
+                    }))?,
+                    "text" => {
+                        let mut lines = vec![format!(
+                            "{} added, {} removed, {} changed",
+                            diff.added.len(),
+                            diff.removed.len(),
+                            diff.changed.len()
+                        )];
+                        if !diff.added.is_empty() {
+                            lines.push(format!("Added: {}", diff.added.join(", ")));
+                        }
+                        if !diff.removed.is_empty() {
+                            lines.push(format!("Removed: {}", diff.removed.join(", ")));
+                        }
+                        for changed in &diff.changed {
+                            lines.push(format!("Changed {}:", changed.short_name));
+                            for change in &changed.changes {
+                                lines.push(format!("  {}: {} -> {}", change.field, change.old, change.new));
+                            }
+                        }
+                        lines.push(format!("Affected position expectations ({}): {}", db.domain(), affected.len()));
+                        for a in &affected {
+                            let scope = if a.isotypes.is_empty() { String::new() } else { format!(" [{}]", a.isotypes.join(",")) };
+                            lines.push(format!("  {} {}{} ({})", a.position, a.modification, scope, a.change));
+                        }
+                        if let Some(changes) = &odd_changes {
+                            lines.push(format!("Odd-status changes: {}", changes.len()));
+                            let status = |odd: bool| if odd { "odd" } else { "not odd" };
+                            for c in changes {
+                                lines.push(format!(
+                                    "  {} ({}): {} -> {} (score {:.2} -> {:.2})",
+                                    c.trna_id,
+                                    c.seq_name,
+                                    status(c.was_odd),
+                                    status(c.now_odd),
+                                    c.old_score,
+                                    c.new_score
+                                ));
+                            }
+                        }
+                        lines.join("\n")
+                    }
+                    _ => return Err(anyhow!("Unknown format: {}. Use 'text' or 'json'", format)),
+                };
+
+                if let Some(output_path) = output {
+                    std::fs::write(&output_path, &output_str)?;
+                    eprintln!("Results written to {}", output_path);
+                } else {
+                    println!("{}", output_str);
+                }
+            }
+        },
     }
 
     Ok(())
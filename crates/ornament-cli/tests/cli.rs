@@ -0,0 +1,39 @@
+//! End-to-end runs of the `ornament` binary
+
+use std::path::PathBuf;
+use std::process::Command;
+
+const HITS: &str = r#"[{"id":"tRNA-Phe-GAA","seq_name":"chrXV","start":1001,"end":1076,"strand":"Plus","score":80.0,"isotype":"Phe","anticodon":"GAA","sequence":"GCGGAUUUAGCUCAGUUGGGAGAGCGCCAGACUGAAGAUCUGGAGGUCCUGUGUUCGAUCCACAGAAUUCGCACCA","structure":"(((((((..((((........)))).(((((.......))))).....(((((.......))))))))))))...."}]"#;
+
+/// Write a test input under the system temp directory
+fn input(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ornament-cli-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_analyze_detects_input_format() {
+    // Scan JSON is recognized by its content, whatever the extension
+    let hits = input("hits.out", HITS);
+    let output = Command::new(env!("CARGO_BIN_EXE_ornament"))
+        .args(["analyze", "--mito", "--input"])
+        .arg(&hits)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let results: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(results["summary"]["total_trnas"], 1);
+
+    // FASTA needs a covariance model to be aligned to
+    let fasta = input("trnas.txt", ">tRNA-Phe-GAA-1-1\nGCGGAUUUAGCUCAGUUGGGAGAGC\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_ornament"))
+        .args(["analyze", "--input"])
+        .arg(&fasta)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--cm is required"));
+}
@@ -1,119 +1,568 @@
 //! Modification compatibility analysis
 
-use super::{TRNAHit, ModCompatibilityResult, ModificationIncompatibility, Severity};
-use crate::modification::{ModificationDatabase, SprinzlMapper};
-use crate::{RnaBase, SprinzlPosition, ConservationLevel};
+use super::{
+    TRNAHit, ModCompatibilityResult, ModificationIncompatibility, IncompatibilityKind,
+    AnalysisOptions, EditState, OddPolicy, PositionVerdict, RuledOut, SequenceAlphabet, SequenceSource,
+    SiteObservation, Verdict,
+};
+use super::scoring::score_losses;
+use crate::modification::{
+    ContextPredicate, Modification, ModificationDatabase, PositionModExpectation, Prerequisite, SprinzlMapper,
+};
+use crate::modification::{decode_residue, BaseCompatibility, Editing, Residue};
+use crate::{IupacBase, RnaBase, SprinzlPosition};
 use crate::modification::Isotype;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 /// Analyze modification compatibility for a tRNA hit
 pub fn analyze_compatibility(
     hit: &TRNAHit,
     mod_db: &ModificationDatabase,
 ) -> ModCompatibilityResult {
-    let mapper = SprinzlMapper::new_standard();
+    analyze_compatibility_with_options(hit, mod_db, &AnalysisOptions::default())
+}
+
+/// Analyze modification compatibility for a tRNA hit with explicit options
+pub fn analyze_compatibility_with_options(
+    hit: &TRNAHit,
+    mod_db: &ModificationDatabase,
+    options: &AnalysisOptions,
+) -> ModCompatibilityResult {
+    let mapper = options.sprinzl_mapper();
+    let policy = options.odd_policy();
+    let source = options.sequence_source();
+
+    // Modification-annotated sequences are analyzed on their parent bases
+    let residues = match options.alphabet() {
+        SequenceAlphabet::Iupac => None,
+        SequenceAlphabet::Modomics => Some(annotated_residues(hit, mod_db)),
+    };
+    let annotated_sequence = residues.is_some().then(|| hit.sequence.clone());
+    let plain;
+    let hit = match &residues {
+        Some(residues) => {
+            plain = TRNAHit { sequence: parent_sequence(residues), ..hit.clone() };
+            &plain
+        }
+        None => hit,
+    };
 
     // Map the sequence to Sprinzl positions using the structure as alignment guide
     // The structure string from cmsearch corresponds to CM columns
     let sprinzl_alignment = map_sequence_to_sprinzl(hit, &mapper);
 
-    // Check each position for modification compatibility
-    let mut incompatibilities = Vec::new();
-    let mut positions_checked = 0;
-    let mut positions_compatible = 0;
+    // Definite bases at each mapped position, for context predicates
+    let mut bases = sprinzl_bases(hit, &sprinzl_alignment);
+    // Sequenced bases, ambiguity codes included, for the sites themselves
+    let mut calls = sprinzl_calls(hit, &sprinzl_alignment);
 
-    // Get isotype for isotype-specific checks
-    let isotype = hit.isotype.as_ref().map(|s| Isotype::new(s));
+    // Get isotype for isotype-specific checks, calling it from the anticodon if needed
+    let isotype = options.isotype_for(hit).map(Isotype::new);
 
-    for (sprinzl_pos, seq_idx) in &sprinzl_alignment {
-        // Get the base at this position
-        let base_char = hit.sequence.chars().nth(*seq_idx);
-        let observed_base = base_char.and_then(RnaBase::from_dna_char);
+    // Transcript-derived sequences: read signature bases as their parent base
+    let signatures = match source {
+        SequenceSource::Genomic => HashMap::new(),
+        SequenceSource::Transcript => signature_reads(mod_db, isotype.as_ref(), &bases),
+    };
+    for (position, (_, modification)) in &signatures {
+        bases.insert(position.clone(), modification.parent_base);
+        calls.insert(position.clone(), modification.parent_base.into());
+    }
 
-        let Some(observed) = observed_base else {
-            continue; // Skip non-standard bases (N, etc.)
+    let mut positions: Vec<&SprinzlPosition> = sprinzl_alignment.keys().collect();
+    positions.sort_by_key(|p| mapper.sort_key(p));
+
+    // Rule each expected alternative in or out from the site base and context
+    let mut checks = Vec::new();
+    let mut not_evaluable = Vec::new();
+    for sprinzl_pos in positions {
+        let Some(&observed) = calls.get(sprinzl_pos).filter(|b| !b.is_unknown()) else {
+            // N or a non-IUPAC symbol: expected sites here cannot be checked
+            let expected = match &isotype {
+                Some(iso) => mod_db.get_expectations_for_isotype(sprinzl_pos, iso),
+                None => mod_db.get_expectations(sprinzl_pos),
+            };
+            if !expected.is_empty() {
+                let mut names: Vec<String> = Vec::new();
+                for name in expected.iter().flat_map(|e| e.names()) {
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+                not_evaluable.push((sprinzl_pos, names));
+            }
+            continue;
         };
 
-        // Get expected modifications at this position
-        let expectations = if let Some(ref iso) = isotype {
-            mod_db.get_expectations_for_isotype(sprinzl_pos, iso)
-        } else {
-            mod_db.get_expectations(sprinzl_pos)
-        };
+        for expectation in applicable_expectations(mod_db, sprinzl_pos, isotype.as_ref(), &bases, source) {
+            let lost_context = expectation.lost_context(&context_bases(expectation, &bases, source));
+            let outcomes = match expectation.editing {
+                // Edited sites are judged on the editing bases, whatever they install
+                Some(editing) => {
+                    let outcome = match lost_context {
+                        Some(predicate) => Outcome::RuledOut(Loss::context(predicate)),
+                        None => edit_outcome(editing, observed),
+                    };
+                    vec![outcome; expectation.names().len()]
+                }
+                None => expectation.modifications.iter()
+                    .map(|modification| match (lost_context, modification.compatibility(observed)) {
+                        (Some(predicate), _) => Outcome::RuledOut(Loss::context(predicate)),
+                        (None, BaseCompatibility::Compatible) => Outcome::RuledIn,
+                        (None, BaseCompatibility::Partial) => Outcome::Partial,
+                        (None, BaseCompatibility::Incompatible) => Outcome::RuledOut(Loss::base_mismatch()),
+                    })
+                    .collect(),
+            };
+            checks.push(ExpectationCheck {
+                position: sprinzl_pos,
+                observed,
+                expectation,
+                uncertain: isotype.is_none() && !expectation.isotypes.is_empty(),
+                outcomes,
+            });
+        }
+    }
+
+    propagate_dependency_losses(&mut checks, mod_db);
 
-        if expectations.is_empty() {
-            continue; // No modification expected here
+    let (mut verdicts, incompatibilities) = position_verdicts(&checks, &not_evaluable, &signatures, mod_db, policy);
+    if let Some(residues) = &residues {
+        for verdict in &mut verdicts {
+            let residue = sprinzl_alignment.get(&verdict.position).and_then(|idx| residues.get(*idx));
+            verdict.observed = residue.and_then(|r| site_observation(verdict, r, mod_db));
         }
+    }
+    let not_evaluable = not_evaluable.len();
+
+    let applicable: Vec<_> = checks.iter()
+        .map(|c| (c.position, c.observed, c.expectation))
+        .collect();
+    let (log_likelihood, contributions) =
+        score_losses(&applicable, &incompatibilities, mod_db, options.scoring_model());
+
+    // Legacy score: compatible share of the positions with a definite verdict
+    let count = |verdict: Verdict| verdicts.iter().filter(|v| v.verdict == verdict).count();
+    let (compatible, incompatible) = (count(Verdict::Compatible), count(Verdict::Incompatible));
+    let compatibility_score = if compatible + incompatible > 0 {
+        compatible as f64 / (compatible + incompatible) as f64
+    } else {
+        1.0 // No positions to check = fully compatible
+    };
+
+    let mut result = ModCompatibilityResult {
+        hit: hit.clone(),
+        annotated_sequence,
+        sprinzl_alignment,
+        incompatibilities,
+        is_odd: false,
+        compatibility_score,
+        log_likelihood,
+        contributions,
+        not_evaluable,
+        verdicts,
+    };
+    result.is_odd = policy.is_odd(&result);
+    result
+}
+
+/// Why an expected alternative is ruled out
+#[derive(Clone)]
+struct Loss {
+    kind: IncompatibilityKind,
+    lost_context: Option<ContextPredicate>,
+    prerequisite: Option<Prerequisite>,
+}
+
+impl Loss {
+    fn base_mismatch() -> Self {
+        Self { kind: IncompatibilityKind::BaseMismatch, lost_context: None, prerequisite: None }
+    }
+
+    fn context(predicate: &ContextPredicate) -> Self {
+        Self { kind: IncompatibilityKind::LostContext, lost_context: Some(predicate.clone()), prerequisite: None }
+    }
+
+    fn dependency(prerequisite: &Prerequisite) -> Self {
+        Self { kind: IncompatibilityKind::DependencyLoss, lost_context: None, prerequisite: Some(prerequisite.clone()) }
+    }
+
+    fn not_editable() -> Self {
+        Self { kind: IncompatibilityKind::NotEditable, lost_context: None, prerequisite: None }
+    }
+}
+
+/// Whether an expected alternative can be present
+#[derive(Clone)]
+enum Outcome {
+    RuledIn,
+    /// The site is an ambiguity code allowing both carrying and other bases
+    Partial,
+    RuledOut(Loss),
+}
+
+/// One applicable expectation checked at a site
+struct ExpectationCheck<'a> {
+    position: &'a SprinzlPosition,
+    observed: IupacBase,
+    expectation: &'a PositionModExpectation,
+    /// Isotype-specific, applied only because the tRNA's isotype is unknown
+    uncertain: bool,
+    /// One outcome per alternative
+    outcomes: Vec<Outcome>,
+}
+
+impl ExpectationCheck<'_> {
+    /// At least one alternative can be present
+    fn satisfied(&self) -> bool {
+        self.outcomes.iter().any(|o| matches!(o, Outcome::RuledIn))
+    }
+
+    /// Every alternative is ruled out
+    fn lost(&self) -> bool {
+        self.outcomes.iter().all(|o| matches!(o, Outcome::RuledOut(_)))
+    }
+}
 
-        positions_checked += 1;
+/// One verdict per checked position, and the incompatibilities behind them
+///
+/// A position is incompatible when an expectation that certainly applies has
+/// every alternative ruled out. It is ambiguous when the only expectations
+/// ruled out apply for want of an isotype call, or when an ambiguity code
+/// leaves an expectation partly compatible. Only incompatible positions report
+/// incompatibilities. Sites read through a sequencing signature report the
+/// base actually sequenced.
+fn position_verdicts(
+    checks: &[ExpectationCheck],
+    not_evaluable: &[(&SprinzlPosition, Vec<String>)],
+    signatures: &HashMap<SprinzlPosition, (RnaBase, &Modification)>,
+    mod_db: &ModificationDatabase,
+    policy: &OddPolicy,
+) -> (Vec<PositionVerdict>, Vec<ModificationIncompatibility>) {
+    let mut verdicts = Vec::new();
+    let mut incompatibilities = Vec::new();
 
-        // Check if the observed base is compatible with any expected modification
-        let mut position_compatible = false;
+    let mut start = 0;
+    while start < checks.len() {
+        let position = checks[start].position;
+        let signature = signatures.get(position);
+        let observed = signature.map_or(checks[start].observed, |(read, _)| (*read).into());
+        let end = start + checks[start..].iter().take_while(|c| c.position == position).count();
+        let at_position = &checks[start..end];
+        start = end;
+        let editing = at_position.iter()
+            .find_map(|c| c.expectation.editing.and_then(|e| edit_state(e, c.observed)));
+
+        let lost: Vec<&ExpectationCheck> = at_position.iter().filter(|c| c.lost()).collect();
+        let verdict = if lost.iter().any(|c| !c.uncertain) {
+            Verdict::Incompatible
+        } else if at_position.iter().any(|c| !c.satisfied()) {
+            Verdict::Ambiguous
+        } else {
+            Verdict::Compatible
+        };
 
-        for expectation in &expectations {
-            for modification in &expectation.modifications {
-                if modification.is_compatible(observed) {
-                    position_compatible = true;
-                    break;
+        let mut ruled_in: Vec<String> = Vec::new();
+        let mut partial: Vec<String> = Vec::new();
+        let mut ruled_out: Vec<RuledOut> = Vec::new();
+        for check in at_position {
+            for (name, outcome) in check.expectation.names().iter().zip(&check.outcomes) {
+                match outcome {
+                    Outcome::RuledIn if !ruled_in.contains(name) => ruled_in.push(name.clone()),
+                    Outcome::Partial if !partial.contains(name) => partial.push(name.clone()),
+                    Outcome::RuledOut(loss) if !ruled_out.iter().any(|r| &r.modification == name) => {
+                        ruled_out.push(RuledOut { modification: name.clone(), kind: loss.kind });
+                    }
+                    _ => {}
                 }
+            }
+        }
 
-                // Found an incompatibility
-                let severity = match expectation.conservation {
-                    ConservationLevel::Universal => Severity::Critical,
-                    ConservationLevel::DomainSpecific => Severity::Major,
-                    ConservationLevel::IsotypeSpecific => Severity::Major,
-                    ConservationLevel::Rare => Severity::Minor,
-                };
-
-                incompatibilities.push(ModificationIncompatibility {
-                    position: sprinzl_pos.clone(),
-                    observed_base: observed,
-                    expected_mod_name: modification.short_name.clone(),
-                    severity,
-                });
+        let first_incompatibility = incompatibilities.len();
+        if verdict == Verdict::Incompatible {
+            for check in lost.iter().filter(|c| !c.uncertain) {
+                let expectation = check.expectation;
+                for (name, outcome) in expectation.names().into_iter().zip(&check.outcomes) {
+                    let Outcome::RuledOut(loss) = outcome else { continue };
+                    let enzymes = match expectation.modifications.iter().find(|m| m.short_name == name) {
+                        Some(modification) => enzyme_ids(mod_db, expectation, modification),
+                        None => expectation.enzymes.clone(),
+                    };
+                    incompatibilities.push(ModificationIncompatibility {
+                        position: position.clone(),
+                        observed_base: observed,
+                        severity: policy.severity_for(expectation, &name),
+                        expected_mod_name: name,
+                        kind: loss.kind,
+                        lost_context: loss.lost_context.clone(),
+                        enzymes,
+                        prerequisite: loss.prerequisite.clone(),
+                        evidence: expectation.evidence.clone(),
+                    });
+                }
             }
+        }
+        let severity = incompatibilities[first_incompatibility..].iter()
+            .map(|i| i.severity)
+            .reduce(|a, b| if a.at_least(b) { a } else { b });
+
+        verdicts.push(PositionVerdict {
+            position: position.clone(),
+            observed_base: Some(observed),
+            verdict,
+            ruled_in,
+            partial,
+            ruled_out,
+            signature: signature.map(|(_, modification)| modification.short_name.clone()),
+            editing,
+            observed: None,
+            severity,
+        });
+    }
+
+    // An unreadable site may or may not carry what is expected there
+    for (position, expected) in not_evaluable {
+        verdicts.push(PositionVerdict {
+            position: (*position).clone(),
+            observed_base: None,
+            verdict: Verdict::NotEvaluable,
+            ruled_in: Vec::new(),
+            partial: expected.clone(),
+            ruled_out: Vec::new(),
+            signature: None,
+            editing: None,
+            observed: None,
+            severity: None,
+        });
+    }
+    let mapper = SprinzlMapper::new_standard();
+    verdicts.sort_by_key(|v| mapper.sort_key(&v.position));
+
+    (verdicts, incompatibilities)
+}
+
+/// Sites whose sequenced base is the sequencing signature of an expected
+/// modification, with that base and the modification
+///
+/// A base some expected modification can carry as read is taken at face value.
+/// Expectations are taken by isotype only: selecting contexts are judged
+/// after the signature bases are read as their parent bases.
+fn signature_reads<'a>(
+    mod_db: &'a ModificationDatabase,
+    isotype: Option<&Isotype>,
+    bases: &HashMap<SprinzlPosition, RnaBase>,
+) -> HashMap<SprinzlPosition, (RnaBase, &'a Modification)> {
+    let mut reads = HashMap::new();
+    for (position, &base) in bases {
+        let expectations = match isotype {
+            Some(iso) => mod_db.get_expectations_for_isotype(position, iso),
+            None => mod_db.get_expectations(position),
+        };
+        // Edited sites are judged on their editing bases instead
+        let mut expected = expectations.iter()
+            .filter(|e| e.editing.is_none())
+            .flat_map(|e| e.modifications.iter());
+        if expected.clone().any(|m| m.is_compatible(base)) {
+            continue;
+        }
+        if let Some(modification) =
+            expected.find(|m| mod_db.signature(&m.short_name).is_some_and(|s| s.reads_as(base)))
+        {
+            reads.insert(position.clone(), (base, modification));
+        }
+    }
+    reads
+}
+
+/// Residues of a sequence in the MODOMICS alphabet, one per character
+fn annotated_residues(hit: &TRNAHit, mod_db: &ModificationDatabase) -> Vec<Residue> {
+    hit.sequence.chars().map(|c| decode_residue(c, mod_db)).collect()
+}
+
+/// The parent bases of annotated residues; unknown codes read as N, gaps are kept
+fn parent_sequence(residues: &[Residue]) -> String {
+    residues.iter()
+        .map(|residue| match residue {
+            Residue::Unknown(c) if matches!(c, '-' | '_' | '.') => *c,
+            residue => residue.base().map_or('N', RnaBase::to_char),
+        })
+        .collect()
+}
 
-            if position_compatible {
-                break;
+/// Whether an annotated residue carries one of a site's expected modifications,
+/// another one or none
+fn site_observation(
+    verdict: &PositionVerdict,
+    residue: &Residue,
+    mod_db: &ModificationDatabase,
+) -> Option<SiteObservation> {
+    match residue {
+        Residue::Base(_) => Some(SiteObservation::Unmodified),
+        Residue::Modified { modification, .. } => {
+            let mut expected = verdict.ruled_in.iter()
+                .chain(&verdict.partial)
+                .chain(verdict.ruled_out.iter().map(|r| &r.modification));
+            let modification = modification.clone();
+            if expected.any(|name| mod_db.same_modification(name, &modification)) {
+                Some(SiteObservation::Expected { modification })
+            } else {
+                Some(SiteObservation::Different { modification })
             }
         }
+        Residue::Unknown(_) => None,
+    }
+}
+
+/// Sequenced bases at each mapped Sprinzl position, ambiguity codes included
+/// (gaps and other non-IUPAC symbols are left out)
+fn sprinzl_calls(
+    hit: &TRNAHit,
+    alignment: &HashMap<SprinzlPosition, usize>,
+) -> HashMap<SprinzlPosition, IupacBase> {
+    let sequence: Vec<char> = hit.sequence.chars().collect();
+    alignment
+        .iter()
+        .filter_map(|(pos, idx)| {
+            let base = sequence.get(*idx).copied().and_then(IupacBase::from_char)?;
+            Some((pos.clone(), base))
+        })
+        .collect()
+}
+
+/// Bases at each mapped Sprinzl position (non-standard bases are left out)
+pub(crate) fn sprinzl_bases(
+    hit: &TRNAHit,
+    alignment: &HashMap<SprinzlPosition, usize>,
+) -> HashMap<SprinzlPosition, RnaBase> {
+    let sequence: Vec<char> = hit.sequence.chars().collect();
+    alignment
+        .iter()
+        .filter_map(|(pos, idx)| {
+            let base = sequence.get(*idx).copied().and_then(RnaBase::from_dna_char)?;
+            Some((pos.clone(), base))
+        })
+        .collect()
+}
+
+/// Expectations at a position that apply to a tRNA, by isotype and selecting context
+pub(crate) fn applicable_expectations<'a>(
+    mod_db: &'a ModificationDatabase,
+    position: &SprinzlPosition,
+    isotype: Option<&Isotype>,
+    bases: &HashMap<SprinzlPosition, RnaBase>,
+    source: SequenceSource,
+) -> Vec<&'a PositionModExpectation> {
+    let expectations = match isotype {
+        Some(iso) => mod_db.get_expectations_for_isotype(position, iso),
+        None => mod_db.get_expectations(position),
+    };
+    expectations.into_iter().filter(|exp| exp.applies_to(&context_bases(exp, bases, source))).collect()
+}
 
-        if position_compatible {
-            positions_compatible += 1;
+/// Bases an expectation's context is judged on
+///
+/// A transcript-derived sequence may show an edited site post-editing; its
+/// context is then judged with the site read as its genomic base. A genome
+/// cannot show an edit, so genomic sequences are taken as they are.
+fn context_bases<'a>(
+    expectation: &PositionModExpectation,
+    bases: &'a HashMap<SprinzlPosition, RnaBase>,
+    source: SequenceSource,
+) -> Cow<'a, HashMap<SprinzlPosition, RnaBase>> {
+    match expectation.editing {
+        Some(editing)
+            if source == SequenceSource::Transcript && bases.get(&expectation.position) == Some(&editing.to) =>
+        {
+            let mut unedited = bases.clone();
+            unedited.insert(expectation.position.clone(), editing.from);
+            Cow::Owned(unedited)
         }
+        _ => Cow::Borrowed(bases),
     }
+}
 
-    // Calculate compatibility score
-    let compatibility_score = if positions_checked > 0 {
-        positions_compatible as f64 / positions_checked as f64
+/// Whether an edited site can carry its edit: the genomic or the edited base
+fn edit_outcome(editing: Editing, observed: IupacBase) -> Outcome {
+    let bases = observed.bases();
+    let editable = bases.iter().filter(|b| **b == editing.from || **b == editing.to).count();
+    if editable == bases.len() {
+        Outcome::RuledIn
+    } else if editable == 0 {
+        Outcome::RuledOut(Loss::not_editable())
     } else {
-        1.0 // No positions to check = fully compatible
+        Outcome::Partial
+    }
+}
+
+/// Whether a definite base shows an edited site before or after editing
+fn edit_state(editing: Editing, observed: IupacBase) -> Option<EditState> {
+    match observed.definite()? {
+        base if base == editing.from => Some(EditState::PreEditing),
+        base if base == editing.to => Some(EditState::PostEditing),
+        _ => None,
+    }
+}
+
+fn enzyme_ids(
+    mod_db: &ModificationDatabase,
+    expectation: &PositionModExpectation,
+    modification: &Modification,
+) -> Vec<String> {
+    mod_db.enzymes_for(expectation, modification)
+        .into_iter()
+        .map(|e| e.id.clone())
+        .collect()
+}
+
+/// Rule out expectations whose prerequisite modification is lost
+///
+/// A lost site can block modifications elsewhere (e.g. Cm32 loss blocks yW37),
+/// so losses are followed along dependency edges until nothing new is lost.
+fn propagate_dependency_losses(checks: &mut [ExpectationCheck], mod_db: &ModificationDatabase) {
+    let is_lost = |checks: &[ExpectationCheck], position: &SprinzlPosition, name: &str| {
+        checks.iter().any(|c| {
+            c.position == position
+                && c.lost()
+                && c.expectation.modifications.iter().any(|m| mod_db.same_modification(&m.short_name, name))
+        })
     };
 
-    // Determine if this is an "odd" tRNA
-    // Odd if: score < 1.0 AND has critical/major incompatibilities
-    let has_significant_incompatibility = incompatibilities.iter()
-        .any(|i| matches!(i.severity, Severity::Critical | Severity::Major));
-    let is_odd = compatibility_score < 1.0 && has_significant_incompatibility;
+    loop {
+        let mut changed = false;
+        for i in 0..checks.len() {
+            if checks[i].lost() {
+                continue;
+            }
+            let expectation = checks[i].expectation;
+            let Some(prerequisite) = expectation.depends_on.iter()
+                .find(|p| is_lost(checks, &p.position, &p.modification))
+            else {
+                continue;
+            };
+            for outcome in checks[i].outcomes.iter_mut() {
+                if !matches!(outcome, Outcome::RuledOut(_)) {
+                    *outcome = Outcome::RuledOut(Loss::dependency(prerequisite));
+                }
+            }
+            changed = true;
+        }
 
-    ModCompatibilityResult {
-        hit: hit.clone(),
-        sprinzl_alignment,
-        incompatibilities,
-        is_odd,
-        compatibility_score,
+        if !changed {
+            break;
+        }
     }
 }
 
 /// Map a tRNA sequence to Sprinzl positions
 /// Uses the structure string as an alignment guide
-fn map_sequence_to_sprinzl(
+pub(crate) fn map_sequence_to_sprinzl(
     hit: &TRNAHit,
     mapper: &SprinzlMapper,
 ) -> HashMap<SprinzlPosition, usize> {
     // If structure is available, use it as alignment to CM
     if !hit.structure.is_empty() {
-        return mapper.map_alignment(&hit.structure);
+        return mapper.map_hit(&hit.sequence, &hit.structure);
     }
 
     // Fallback: assume sequence is already aligned to standard positions
@@ -131,10 +580,19 @@ fn map_sequence_to_sprinzl(
 pub fn analyze_batch(
     hits: &[TRNAHit],
     mod_db: &ModificationDatabase,
+) -> BatchAnalysisResult {
+    analyze_batch_with_options(hits, mod_db, &AnalysisOptions::default())
+}
+
+/// Analyze multiple tRNA hits with explicit options
+pub fn analyze_batch_with_options(
+    hits: &[TRNAHit],
+    mod_db: &ModificationDatabase,
+    options: &AnalysisOptions,
 ) -> BatchAnalysisResult {
     let results: Vec<ModCompatibilityResult> = hits
         .iter()
-        .map(|hit| analyze_compatibility(hit, mod_db))
+        .map(|hit| analyze_compatibility_with_options(hit, mod_db, options))
         .collect();
 
     let total = results.len();
@@ -145,14 +603,53 @@ pub fn analyze_batch(
         1.0
     };
 
+    let enzyme_losses = summarize_enzyme_losses(&results);
+    let average_log_likelihood = if total > 0 {
+        results.iter().map(|r| r.log_likelihood).sum::<f64>() / total as f64
+    } else {
+        0.0
+    };
+
     BatchAnalysisResult {
         results,
         total_trnas: total,
         odd_trnas: odd_count,
         average_compatibility: avg_score,
+        average_log_likelihood,
+        enzyme_losses,
     }
 }
 
+/// Count the tRNAs and sites each enzyme loses, most affected enzyme first
+fn summarize_enzyme_losses(results: &[ModCompatibilityResult]) -> Vec<EnzymeLoss> {
+    let mut sites: BTreeMap<&str, BTreeSet<(&str, &str)>> = BTreeMap::new();
+    for result in results {
+        for incompatibility in &result.incompatibilities {
+            for enzyme in &incompatibility.enzymes {
+                sites
+                    .entry(enzyme.as_str())
+                    .or_default()
+                    .insert((result.hit.id.as_str(), incompatibility.position.0.as_str()));
+            }
+        }
+    }
+
+    let mut losses: Vec<EnzymeLoss> = sites
+        .into_iter()
+        .map(|(enzyme, sites)| {
+            let trna_ids: BTreeSet<&str> = sites.iter().map(|(id, _)| *id).collect();
+            EnzymeLoss {
+                enzyme: enzyme.to_string(),
+                trnas: trna_ids.len(),
+                sites: sites.len(),
+                trna_ids: trna_ids.into_iter().map(str::to_string).collect(),
+            }
+        })
+        .collect();
+    losses.sort_by(|a, b| b.trnas.cmp(&a.trnas).then_with(|| a.enzyme.cmp(&b.enzyme)));
+    losses
+}
+
 /// Result of batch analysis
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BatchAnalysisResult {
@@ -160,12 +657,38 @@ pub struct BatchAnalysisResult {
     pub total_trnas: usize,
     pub odd_trnas: usize,
     pub average_compatibility: f64,
+    #[serde(default)]
+    pub average_log_likelihood: f64,
+    /// Per-enzyme counts of lost substrate sites
+    #[serde(default)]
+    pub enzyme_losses: Vec<EnzymeLoss>,
+}
+
+impl BatchAnalysisResult {
+    /// Results from most to least anomalous by log-likelihood
+    pub fn ranked(&self) -> Vec<&ModCompatibilityResult> {
+        let mut ranked: Vec<&ModCompatibilityResult> = self.results.iter().collect();
+        ranked.sort_by(|a, b| a.log_likelihood.total_cmp(&b.log_likelihood));
+        ranked
+    }
+}
+
+/// Substrate sites of one enzyme lost across a batch of tRNAs
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct EnzymeLoss {
+    pub enzyme: String,
+    /// Number of tRNAs with at least one lost site
+    pub trnas: usize,
+    /// Number of lost (tRNA, position) sites
+    pub sites: usize,
+    pub trna_ids: Vec<String>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::analysis::Strand;
+    use crate::analysis::test_support::{arm_options, phe_hit};
+    use crate::analysis::{Severity, Strand};
 
     #[test]
     fn test_analyze_compatibility_compatible() {
@@ -212,4 +735,268 @@ mod tests {
 
         assert_eq!(batch_result.total_trnas, 1);
     }
+
+    #[test]
+    fn test_context_predicates() {
+        // Anticodon GAA, G37, A38
+        let hit = |isotype: &str| phe_hit(&format!("test-{}", isotype), Some(isotype), &[]);
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+
+        // Q34 needs a GUN anticodon: a tRNA-Tyr reading GAA has lost it
+        let result = analyze_compatibility_with_options(&hit("Tyr"), &db, &options);
+        let lost = result.incompatibilities.iter()
+            .find(|i| i.position.0 == "34")
+            .expect("Q34 incompatibility");
+        assert_eq!(lost.kind, IncompatibilityKind::LostContext);
+        assert_eq!(lost.lost_context.as_ref().map(|p| p.to_string()).as_deref(), Some("anticodon GUN"));
+
+        // t6A37 only applies to U36 tRNAs, so G37 here is not a base mismatch
+        let result = analyze_compatibility_with_options(&hit("Ile"), &db, &options);
+        assert!(result.incompatibilities.iter().all(|i| i.position.0 != "37"));
+    }
+
+    #[test]
+    fn test_position_verdicts() {
+        // Anticodon GAA and an unreadable base at 55
+        let hit = |isotype: Option<&str>| phe_hit("verdicts", isotype, &[(54, 'N')]);
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+        let verdict_at = |result: &ModCompatibilityResult, pos: &str| {
+            result.verdicts.iter().find(|v| v.position.0 == pos).cloned().unwrap()
+        };
+
+        // Without an isotype, Q34 applies only for want of one: ruled out, but
+        // Gm34 is ruled in, so the site is ambiguous and not an incompatibility
+        let result = analyze_compatibility_with_options(&hit(None), &db, &options);
+        let q34 = verdict_at(&result, "34");
+        assert_eq!(q34.verdict, Verdict::Ambiguous);
+        assert_eq!(q34.ruled_in, ["Gm"]);
+        assert_eq!(q34.ruled_out, [RuledOut { modification: "Q".to_string(), kind: IncompatibilityKind::LostContext }]);
+        assert!(result.incompatibilities.iter().all(|i| i.position.0 != "34"));
+        assert_eq!(verdict_at(&result, "55").verdict, Verdict::NotEvaluable);
+
+        // A tRNA-Tyr certainly expects Q34
+        let result = analyze_compatibility_with_options(&hit(Some("Tyr")), &db, &options);
+        assert_eq!(verdict_at(&result, "34").verdict, Verdict::Incompatible);
+        assert_eq!(verdict_at(&result, "34").severity, Some(Severity::Major));
+
+        // One verdict per position, and every incompatibility sits on an incompatible one
+        let mut positions: Vec<&str> = result.verdicts.iter().map(|v| v.position.0.as_str()).collect();
+        positions.dedup();
+        assert_eq!(positions.len(), result.verdicts.len());
+        assert!(result.incompatibilities.iter().all(|i| verdict_at(&result, &i.position.0).verdict == Verdict::Incompatible));
+    }
+
+    #[test]
+    fn test_ambiguous_bases() {
+        // The Psi55 site (index 54 under arm numbering) read as various codes
+        let hit = |code: char| phe_hit(&format!("psi55-{}", code), Some("Phe"), &[(54, code)]);
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+        let psi55 = |code: char| {
+            let result = analyze_compatibility_with_options(&hit(code), &db, &options);
+            let verdict = result.verdicts.iter().find(|v| v.position.0 == "55").cloned().unwrap();
+            (verdict, result.not_evaluable)
+        };
+
+        // Y (C or U) may be the U Psi needs; R (A or G) cannot be
+        let (verdict, _) = psi55('Y');
+        assert_eq!(verdict.verdict, Verdict::Ambiguous);
+        assert_eq!(verdict.partial, ["Psi"]);
+        assert_eq!(psi55('R').0.verdict, Verdict::Incompatible);
+        // Soft-masked bases are read as usual
+        assert_eq!(psi55('u').0.verdict, Verdict::Compatible);
+
+        let (verdict, not_evaluable) = psi55('N');
+        assert_eq!(verdict.verdict, Verdict::NotEvaluable);
+        assert_eq!(verdict.partial, ["Psi"]);
+        assert_eq!(not_evaluable, 1);
+
+        let code = IupacBase::from_char('y').unwrap();
+        assert_eq!(code.to_char(), 'Y');
+        assert_eq!(code.bases(), [RnaBase::C, RnaBase::U]);
+        assert_eq!(serde_json::to_string(&code).unwrap(), "\"Y\"");
+    }
+
+    #[test]
+    fn test_transcript_signatures() {
+        // G at m1A58 (index 57 under arm numbering), as a tRNA-seq consensus
+        // would read it; C at Psi55 has no signature
+        let hit = |changes: &[(usize, char)]| phe_hit("tRNA-Phe", Some("Phe"), changes);
+        let db = ModificationDatabase::eukaryotic();
+        let genomic = arm_options();
+        let transcript = genomic.clone().with_sequence_source(SequenceSource::Transcript);
+        let verdict = |result: &ModCompatibilityResult, pos: &str| {
+            result.verdicts.iter().find(|v| v.position.0 == pos).cloned().unwrap()
+        };
+
+        let m1a = hit(&[(57, 'G')]);
+        let as_genome = analyze_compatibility_with_options(&m1a, &db, &genomic);
+        assert_eq!(verdict(&as_genome, "58").verdict, Verdict::Incompatible);
+
+        let as_transcript = analyze_compatibility_with_options(&m1a, &db, &transcript);
+        let m1a58 = verdict(&as_transcript, "58");
+        assert_eq!(m1a58.verdict, Verdict::Compatible);
+        assert_eq!(m1a58.signature.as_deref(), Some("m1A"));
+        assert_eq!(m1a58.observed_base, IupacBase::from_char('G'));
+        assert!(as_transcript.incompatibilities.iter().all(|i| i.position.0 != "58"));
+
+        let psi = analyze_compatibility_with_options(&hit(&[(54, 'C')]), &db, &transcript);
+        assert_eq!(verdict(&psi, "55").verdict, Verdict::Incompatible);
+        assert_eq!(verdict(&psi, "55").signature, None);
+    }
+
+    #[test]
+    fn test_editing() {
+        // Index 33 is position 34 and index 31 position 32 under arm numbering;
+        // no anticodon call, so the sites are read from the sequence alone
+        let hit = |isotype: &str, changes: &[(usize, char)]| TRNAHit {
+            anticodon: None,
+            ..phe_hit(&format!("tRNA-{}", isotype), Some(isotype), changes)
+        };
+        let genomic = arm_options();
+        let transcript = genomic.clone().with_sequence_source(SequenceSource::Transcript);
+        let at = |db: &ModificationDatabase, hit: &TRNAHit, options: &AnalysisOptions, pos: &str| {
+            let result = analyze_compatibility_with_options(hit, db, options);
+            let verdict = result.verdicts.iter().find(|v| v.position.0 == pos).cloned();
+            (verdict, result.incompatibilities)
+        };
+
+        // A34 of tRNA-Ala(AGC) is edited to I34, read as G once edited; the
+        // bundled entry selects the NGC box, so C34 cannot be edited
+        let db = ModificationDatabase::eukaryotic();
+        let ala = |base: char| hit("Ala", &[(33, base), (34, 'G'), (35, 'C')]);
+        let (i34, _) = at(&db, &ala('A'), &genomic, "34");
+        let i34 = i34.unwrap();
+        assert_eq!((i34.verdict, i34.editing), (Verdict::Compatible, Some(EditState::PreEditing)));
+        assert_eq!(i34.ruled_in, ["I"]);
+        for options in [&genomic, &transcript] {
+            let (i34, _) = at(&db, &ala('G'), options, "34");
+            let i34 = i34.unwrap();
+            assert_eq!((i34.verdict, i34.editing), (Verdict::Compatible, Some(EditState::PostEditing)));
+        }
+        let (c34, lost) = at(&db, &ala('C'), &genomic, "34");
+        assert_eq!(c34.unwrap().verdict, Verdict::Incompatible);
+        let lost = lost.iter().find(|i| i.position.0 == "34").unwrap();
+        assert_eq!((lost.expected_mod_name.as_str(), lost.kind), ("I", IncompatibilityKind::NotEditable));
+
+        // A C-to-U site installing no modification
+        let file = crate::modification::ExpectationFile::from_toml_str(r#"
+            schema_version = 1
+            [[expectations]]
+            position = "32"
+            modifications = []
+            editing = { from = "C", to = "U" }
+            conservation = "IsotypeSpecific"
+            functional_role = "AnticodonFunction"
+            isotypes = ["Thr"]
+        "#).unwrap();
+        let mut db = db;
+        db.replace_expectations(&file).unwrap();
+        let (c32, _) = at(&db, &hit("Thr", &[]), &genomic, "32");
+        assert_eq!(c32.unwrap().editing, Some(EditState::PreEditing));
+        let (u32, _) = at(&db, &hit("Thr", &[(31, 'U')]), &genomic, "32");
+        assert_eq!(u32.unwrap().editing, Some(EditState::PostEditing));
+        let (a32, lost) = at(&db, &hit("Thr", &[(31, 'A')]), &genomic, "32");
+        assert_eq!(a32.unwrap().verdict, Verdict::Incompatible);
+        assert_eq!(lost[0].expected_mod_name, "C-to-U");
+        assert_eq!(lost[0].kind, IncompatibilityKind::NotEditable);
+    }
+
+    #[test]
+    fn test_annotated_sequences() {
+        // MODOMICS alphabet: Ψ55 (index 54), D at m1A58 (index 57), " for m1A
+        let hit = |changes: &[(usize, char)]| phe_hit("annotated", Some("Phe"), changes);
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options().with_alphabet(SequenceAlphabet::Modomics);
+        let observed = |result: &ModCompatibilityResult, pos: &str| {
+            result.verdicts.iter().find(|v| v.position.0 == pos).and_then(|v| v.observed.clone())
+        };
+
+        let result = analyze_compatibility_with_options(&hit(&[(54, 'Ψ'), (57, '"')]), &db, &options);
+        assert_eq!(result.hit.sequence.chars().nth(54), Some('U'));
+        let annotated = result.annotated_sequence.as_deref().unwrap();
+        assert_eq!((annotated.chars().nth(54), annotated.chars().nth(57)), (Some('Ψ'), Some('"')));
+        assert_eq!(observed(&result, "55"), Some(SiteObservation::Expected { modification: "Psi".to_string() }));
+        assert_eq!(observed(&result, "58"), Some(SiteObservation::Expected { modification: "m1A".to_string() }));
+        // Same verdicts as the plain sequence
+        let plain = analyze_compatibility_with_options(&hit(&[]), &db, &options);
+        assert_eq!(result.incompatibilities.len(), plain.incompatibilities.len());
+        assert_eq!(observed(&plain, "55"), Some(SiteObservation::Unmodified));
+        assert_eq!(plain.annotated_sequence.as_deref(), Some(plain.hit.sequence.as_str()));
+        let iupac = analyze_compatibility_with_options(&hit(&[]), &db, &arm_options());
+        assert_eq!(iupac.annotated_sequence, None);
+
+        // D in place of Psi: a U-derived modification, but not the expected one
+        let result = analyze_compatibility_with_options(&hit(&[(54, 'D')]), &db, &options);
+        let d55 = result.verdicts.iter().find(|v| v.position.0 == "55").unwrap();
+        assert_eq!(d55.verdict, Verdict::Compatible);
+        assert_eq!(d55.observed.as_ref().and_then(SiteObservation::modification), Some("D"));
+        assert!(matches!(d55.observed, Some(SiteObservation::Different { .. })));
+    }
+
+    #[test]
+    fn test_enzyme_losses() {
+        // C at index 53 loses U54
+        let hits = vec![
+            phe_hit("a", Some("Tyr"), &[]),
+            phe_hit("b", Some("Phe"), &[(53, 'C')]),
+            phe_hit("c", Some("Tyr"), &[(53, 'C')]),
+        ];
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+        let batch = analyze_batch_with_options(&hits, &db, &options);
+
+        let loss = |id: &str| batch.enzyme_losses.iter().find(|l| l.enzyme == id).unwrap();
+        assert_eq!(loss("TrmA").trna_ids, ["b", "c"]);
+        assert_eq!(loss("Tgt").trna_ids, ["a", "c"]);
+        assert_eq!(loss("Tgt").sites, 2);
+    }
+
+    #[test]
+    fn test_dependency_loss() {
+        // tRNA-Phe with a purine at 32: Trm7 cannot make Cm32, so yW37 is lost too
+        let hit = phe_hit("Phe-A32", Some("Phe"), &[(31, 'A')]);
+        let db = ModificationDatabase::eukaryotic();
+        let result = analyze_compatibility_with_options(&hit, &db, &arm_options());
+
+        let yw = result.incompatibilities.iter()
+            .find(|i| i.expected_mod_name == "yW")
+            .expect("yW37 dependency loss");
+        assert_eq!(yw.kind, IncompatibilityKind::DependencyLoss);
+        assert_eq!(yw.prerequisite.as_ref().map(|p| p.to_string()).as_deref(), Some("Cm32"));
+        assert_eq!(yw.enzymes, ["TYW"]);
+        // Flagged sites carry the expectation's supporting evidence
+        let evidence = yw.evidence.as_ref().unwrap();
+        assert_eq!(evidence.code, crate::modification::EvidenceCode::DirectSequencing);
+        assert!(evidence.organisms.contains(&"Saccharomyces cerevisiae".to_string()));
+        // Every bundled site says how it is known
+        assert!(db.expectations().iter().all(|e| e.evidence.is_some()));
+    }
+
+    #[test]
+    fn test_mitochondrial_isotype_call() {
+        // Anticodon UCA reads UGA, which is Trp in vertebrate mitochondria
+        let hit = TRNAHit {
+            id: "mt-Trp".to_string(),
+            seq_name: "chrM".to_string(),
+            start: 5512,
+            end: 5579,
+            strand: Strand::Plus,
+            score: 40.0,
+            isotype: None,
+            anticodon: Some("UCA".to_string()),
+            sequence: "AGAAAUUUAGGUUAAAUACAGACCAAGAGCCUUCAAAGCCCUCAGUAAGUUGCAAUACUUAAUUUCUG".to_string(),
+            structure: String::new(),
+        };
+
+        assert_eq!(AnalysisOptions::default().isotype_for(&hit), None);
+        let options = AnalysisOptions::mitochondrial();
+        assert_eq!(options.isotype_for(&hit).as_deref(), Some("Trp"));
+
+        let db = ModificationDatabase::mitochondrial();
+        let result = analyze_compatibility_with_options(&hit, &db, &options);
+        assert!(!result.sprinzl_alignment.is_empty());
+    }
 }
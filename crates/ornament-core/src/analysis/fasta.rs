@@ -0,0 +1,99 @@
+//! tRNA hits from plain FASTA
+//!
+//! Sequences without coordinates or structure (GtRNAdb downloads, assemblies)
+//! are aligned to a covariance model with cmalign (see
+//! [`InfernalRunner::cmalign`]). The alignment gives each sequence a
+//! structure, and the structure's arms give Sprinzl numbering and an
+//! anticodon. GtRNAdb names ("Homo_sapiens_tRNA-Ala-AGC-1-1") can supply the
+//! isotype and anticodon instead.
+//!
+//! [`InfernalRunner::cmalign`]: crate::infernal::InfernalRunner::cmalign
+
+use super::{Strand, TRNAHit};
+use crate::infernal::AlignedSequence;
+use crate::modification::{Isotype, SprinzlMapper};
+use crate::SprinzlPosition;
+
+/// A tRNA hit for an aligned sequence, identified by its FASTA ID
+///
+/// The anticodon is read at Sprinzl 34-36 as numbered from the structure's
+/// arms; the isotype is left for the analysis to call from it.
+pub fn hit_from_alignment(aligned: &AlignedSequence) -> TRNAHit {
+    let sequence = aligned.sequence();
+    let structure = aligned.structure();
+    let residues: Vec<char> = sequence.chars().collect();
+
+    let mapping = SprinzlMapper::new_mitochondrial().map_hit(&sequence, &structure);
+    let anticodon: Option<String> = (34..=36)
+        .map(|n| {
+            let idx = mapping.get(&SprinzlPosition::from_num(n))?;
+            residues.get(*idx).copied().filter(|c| matches!(c, 'A' | 'C' | 'G' | 'U'))
+        })
+        .collect();
+
+    TRNAHit {
+        id: aligned.name.clone(),
+        seq_name: aligned.name.clone(),
+        start: 1,
+        end: residues.len(),
+        strand: Strand::Plus,
+        score: aligned.score.unwrap_or(0.0),
+        isotype: None,
+        anticodon,
+        sequence,
+        structure,
+    }
+}
+
+/// Isotype and anticodon (as RNA) from a GtRNAdb-style name
+///
+/// Reads `tRNA-<isotype>-<anticodon>` anywhere in the name, as in
+/// "Homo_sapiens_tRNA-Ala-AGC-1-1" or "tRNA-iMet-CAT-1-1".
+pub fn parse_gtrnadb_name(name: &str) -> Option<(String, String)> {
+    let rest = &name[name.find("tRNA-")? + "tRNA-".len()..];
+    let mut parts = rest.split(['-', ' ', '|']);
+    let isotype = parts.next()?;
+    let anticodon = parts.next()?;
+    let valid = Isotype::ALL.contains(&isotype)
+        && anticodon.len() == 3
+        && anticodon.chars().all(|c| matches!(c, 'A' | 'C' | 'G' | 'T' | 'U'));
+    valid.then(|| (isotype.to_string(), anticodon.replace('T', "U")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_support::{PHE_SEQUENCE, PHE_STRUCTURE};
+
+    #[test]
+    fn test_hit_from_alignment() {
+        // tRNA-Phe as cmalign would write it: WUSS brackets, a deleted column
+        // and a lowercase insert in the variable loop
+        let (sequence, structure) = (PHE_SEQUENCE, PHE_STRUCTURE);
+        let mut aligned: String = sequence[..45].to_string();
+        let mut ss_cons: String = structure[..45].replace('(', "<").replace(')', ">");
+        aligned.push('-');
+        ss_cons.push(',');
+        aligned.push_str(&sequence[45..47].to_lowercase());
+        ss_cons.push_str("..");
+        aligned.push_str(&sequence[47..]);
+        ss_cons.push_str(&structure[47..].replace('(', "<").replace(')', ">"));
+
+        let hit = hit_from_alignment(&AlignedSequence {
+            name: "Saccharomyces_cerevisiae_tRNA-Phe-GAA-1-1".to_string(),
+            aligned,
+            ss_cons,
+            score: Some(71.2),
+        });
+        assert_eq!(hit.sequence, sequence);
+        assert_eq!(hit.structure, structure);
+        assert_eq!(hit.anticodon.as_deref(), Some("GAA"));
+        assert_eq!(hit.isotype, None);
+        assert_eq!((hit.start, hit.end, hit.score), (1, 76, 71.2));
+
+        assert_eq!(parse_gtrnadb_name(&hit.id), Some(("Phe".to_string(), "GAA".to_string())));
+        assert_eq!(parse_gtrnadb_name("tRNA-iMet-CAT-1-1"), Some(("iMet".to_string(), "CAU".to_string())));
+        assert_eq!(parse_gtrnadb_name("chr1.trna5"), None);
+        assert_eq!(parse_gtrnadb_name("tRNA-Xaa-GAA-1-1"), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! Genetic codes for isotype calls
+//!
+//! Translates a tRNA anticodon to the amino acid it decodes. Mitochondrial
+//! codes reassign codons (e.g. UGA -> Trp, AUA -> Met in vertebrates), so the
+//! isotype of an organellar tRNA has to be called with the matching table.
+
+use serde::{Deserialize, Serialize};
+
+/// Codon order used by the NCBI translation tables (TCAG x TCAG x TCAG)
+const NCBI_BASES: [char; 4] = ['U', 'C', 'A', 'G'];
+
+/// NCBI translation table 1 (standard code)
+const STANDARD_AAS: &str = "FFLLSSSSYY**CC*WLLLLPPPPHHQQRRRRIIIMTTTTNNKKSSRRVVVVAAAADDEEGGGG";
+
+/// NCBI translation table 2 (vertebrate mitochondrial code)
+const VERTEBRATE_MITO_AAS: &str = "FFLLSSSSYY**CCWWLLLLPPPPHHQQRRRRIIMMTTTTNNKKSS**VVVVAAAADDEEGGGG";
+
+/// A genetic code used to call tRNA isotypes from anticodons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GeneticCode {
+    /// NCBI table 1
+    #[default]
+    Standard,
+    /// NCBI table 2
+    VertebrateMitochondrial,
+}
+
+impl GeneticCode {
+    /// NCBI translation table ID
+    pub fn ncbi_table(self) -> u8 {
+        match self {
+            GeneticCode::Standard => 1,
+            GeneticCode::VertebrateMitochondrial => 2,
+        }
+    }
+
+    fn amino_acids(self) -> &'static [u8] {
+        match self {
+            GeneticCode::Standard => STANDARD_AAS.as_bytes(),
+            GeneticCode::VertebrateMitochondrial => VERTEBRATE_MITO_AAS.as_bytes(),
+        }
+    }
+
+    /// Translate a codon (DNA or RNA) to its one-letter amino acid ('*' = stop)
+    pub fn translate(self, codon: &str) -> Option<char> {
+        let chars: Vec<char> = codon.chars().map(|c| c.to_ascii_uppercase()).collect();
+        if chars.len() != 3 {
+            return None;
+        }
+
+        let mut idx = 0;
+        for c in chars {
+            let c = if c == 'T' { 'U' } else { c };
+            idx = idx * 4 + NCBI_BASES.iter().position(|&b| b == c)?;
+        }
+        Some(self.amino_acids()[idx] as char)
+    }
+
+    /// Call the isotype decoded by an anticodon (written 5' to 3')
+    ///
+    /// Returns None for anticodons reading stop codons or containing non-ACGU bases.
+    pub fn isotype_for_anticodon(self, anticodon: &str) -> Option<&'static str> {
+        let codon: String = anticodon
+            .chars()
+            .rev()
+            .map(|c| match c.to_ascii_uppercase() {
+                'A' => 'U',
+                'C' => 'G',
+                'G' => 'C',
+                'T' | 'U' => 'A',
+                other => other,
+            })
+            .collect();
+
+        isotype_for_amino_acid(self.translate(&codon)?)
+    }
+}
+
+/// Map a one-letter amino acid code to its three-letter isotype name
+fn isotype_for_amino_acid(aa: char) -> Option<&'static str> {
+    use crate::modification::Isotype;
+
+    Some(match aa {
+        'A' => Isotype::ALA,
+        'R' => Isotype::ARG,
+        'N' => Isotype::ASN,
+        'D' => Isotype::ASP,
+        'C' => Isotype::CYS,
+        'Q' => Isotype::GLN,
+        'E' => Isotype::GLU,
+        'G' => Isotype::GLY,
+        'H' => Isotype::HIS,
+        'I' => Isotype::ILE,
+        'L' => Isotype::LEU,
+        'K' => Isotype::LYS,
+        'M' => Isotype::MET,
+        'F' => Isotype::PHE,
+        'P' => Isotype::PRO,
+        'S' => Isotype::SER,
+        'T' => Isotype::THR,
+        'W' => Isotype::TRP,
+        'Y' => Isotype::TYR,
+        'V' => Isotype::VAL,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_code() {
+        assert_eq!(GeneticCode::Standard.translate("ATG"), Some('M'));
+        assert_eq!(GeneticCode::Standard.translate("UGA"), Some('*'));
+        assert_eq!(GeneticCode::Standard.isotype_for_anticodon("AGC"), Some("Ala"));
+        assert_eq!(GeneticCode::Standard.isotype_for_anticodon("TCA"), None);
+    }
+
+    #[test]
+    fn test_vertebrate_mito_reassignments() {
+        let code = GeneticCode::VertebrateMitochondrial;
+        // UGA reads Trp, AUA reads Met, AGR is a stop
+        assert_eq!(code.isotype_for_anticodon("UCA"), Some("Trp"));
+        assert_eq!(code.isotype_for_anticodon("UAU"), Some("Met"));
+        assert_eq!(code.isotype_for_anticodon("UCU"), None);
+        assert_eq!(code.isotype_for_anticodon("GCU"), Some("Ser"));
+    }
+}
@@ -0,0 +1,365 @@
+//! Enzyme knockout simulation
+//!
+//! Predicts which modification sites disappear when writer enzymes are deleted:
+//! the sites the enzymes install directly, plus any site that depends on one of
+//! them. The predicted-absent table can be checked against modkit calls from
+//! the mutant strain.
+
+use super::compatibility::{
+    analyze_compatibility_with_options, applicable_expectations, map_sequence_to_sprinzl, sprinzl_bases,
+};
+use super::{AnalysisOptions, Strand, TRNAHit};
+use crate::integration::BedMethylRecord;
+use crate::modification::{
+    Enzyme, Isotype, Modification, ModificationDatabase, PositionModExpectation, Prerequisite,
+};
+use crate::SprinzlPosition;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+const TABLE_HEADER: &str = "#chrom\tstart\tend\tstrand\ttrna_id\tposition\tmodification\tenzyme\tprerequisite";
+
+/// A modification site predicted to be absent in a knockout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PredictedAbsence {
+    pub trna_id: String,
+    pub seq_name: String,
+    /// 0-based genomic coordinate of the site, if the hit has coordinates
+    pub genomic_position: Option<usize>,
+    pub strand: Strand,
+    pub position: SprinzlPosition,
+    pub modification: String,
+    /// The knocked-out enzyme responsible
+    pub enzyme: String,
+    /// The lost upstream modification, for indirect losses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prerequisite: Option<Prerequisite>,
+}
+
+/// Predict the modification sites lost in tRNAs when enzymes are knocked out
+///
+/// Only sites that can carry their modification in the wild type count; sites
+/// already incompatible there are not knockout phenotypes.
+pub fn simulate_knockout(
+    hits: &[TRNAHit],
+    mod_db: &ModificationDatabase,
+    options: &AnalysisOptions,
+    enzymes: &[&Enzyme],
+) -> Vec<PredictedAbsence> {
+    let knocked_out: HashSet<&str> = enzymes.iter().map(|e| e.id.as_str()).collect();
+    let mapper = options.sprinzl_mapper();
+    let mut absences = Vec::new();
+
+    for hit in hits {
+        let wild_type = analyze_compatibility_with_options(hit, mod_db, options);
+        let wt_lost: HashSet<(&SprinzlPosition, &str)> = wild_type
+            .incompatibilities
+            .iter()
+            .map(|i| (&i.position, i.expected_mod_name.as_str()))
+            .collect();
+
+        let alignment = map_sequence_to_sprinzl(hit, &mapper);
+        let bases = sprinzl_bases(hit, &alignment);
+        let isotype = options.isotype_for(hit).map(Isotype::new);
+
+        let mut positions: Vec<&SprinzlPosition> = bases.keys().collect();
+        positions.sort_by_key(|p| mapper.sort_key(p));
+
+        // Sites modified in the wild type
+        let mut sites: Vec<(&SprinzlPosition, &PositionModExpectation, &Modification)> = Vec::new();
+        for position in positions {
+            let observed = bases[position];
+            for expectation in applicable_expectations(mod_db, position, isotype.as_ref(), &bases, options.sequence_source()) {
+                if expectation.lost_context(&bases).is_some() {
+                    continue;
+                }
+                for modification in &expectation.modifications {
+                    let name = modification.short_name.as_str();
+                    if modification.is_compatible(observed) && !wt_lost.contains(&(position, name)) {
+                        sites.push((position, expectation, modification));
+                    }
+                }
+            }
+        }
+
+        let absence = |position: &SprinzlPosition, modification: &str, enzyme: &str, prerequisite| {
+            PredictedAbsence {
+                trna_id: hit.id.clone(),
+                seq_name: hit.seq_name.clone(),
+                genomic_position: alignment.get(position).and_then(|idx| hit.genomic_position(*idx)),
+                strand: hit.strand,
+                position: position.clone(),
+                modification: modification.to_string(),
+                enzyme: enzyme.to_string(),
+                prerequisite,
+            }
+        };
+
+        // Sites the knocked-out enzymes install themselves
+        let mut lost: Vec<PredictedAbsence> = Vec::new();
+        for (position, expectation, modification) in &sites {
+            if let Some(enzyme) = mod_db
+                .enzymes_for(expectation, modification)
+                .into_iter()
+                .find(|e| knocked_out.contains(e.id.as_str()))
+            {
+                lost.push(absence(position, &modification.short_name, &enzyme.id, None));
+            }
+        }
+
+        // Sites that build on a lost one
+        loop {
+            let is_lost = |lost: &[PredictedAbsence], position: &SprinzlPosition, name: &str| {
+                lost.iter().any(|a| &a.position == position && mod_db.same_modification(&a.modification, name))
+            };
+            let mut downstream = Vec::new();
+            for (position, expectation, modification) in &sites {
+                let name = modification.short_name.as_str();
+                if is_lost(&lost, position, name) || is_lost(&downstream, position, name) {
+                    continue;
+                }
+                let upstream = expectation.depends_on.iter().find_map(|p| {
+                    lost.iter()
+                        .find(|a| a.position == p.position && mod_db.same_modification(&a.modification, &p.modification))
+                        .map(|a| (p, a.enzyme.clone()))
+                });
+                if let Some((prerequisite, enzyme)) = upstream {
+                    downstream.push(absence(position, name, &enzyme, Some(prerequisite.clone())));
+                }
+            }
+            if downstream.is_empty() {
+                break;
+            }
+            lost.extend(downstream);
+        }
+
+        absences.extend(lost);
+    }
+
+    absences
+}
+
+/// Write predicted absences as a tab-separated table
+pub fn write_absence_table(absences: &[PredictedAbsence]) -> String {
+    let mut lines = vec![TABLE_HEADER.to_string()];
+    for a in absences {
+        let (start, end) = match a.genomic_position {
+            Some(pos) => (pos.to_string(), (pos + 1).to_string()),
+            None => (".".to_string(), ".".to_string()),
+        };
+        lines.push(format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            a.seq_name,
+            start,
+            end,
+            match a.strand {
+                Strand::Plus => '+',
+                Strand::Minus => '-',
+            },
+            a.trna_id,
+            a.position,
+            a.modification,
+            a.enzyme,
+            a.prerequisite.as_ref().map(|p| p.to_string()).unwrap_or_else(|| ".".to_string()),
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Parse a table written by [`write_absence_table`]
+pub fn parse_absence_table(content: &str) -> Result<Vec<PredictedAbsence>, String> {
+    let mut absences = Vec::new();
+
+    for (n, line) in content.lines().enumerate() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 9 {
+            return Err(format!("line {}: expected 9 columns, found {}", n + 1, fields.len()));
+        }
+
+        let genomic_position = match fields[1] {
+            "." => None,
+            s => Some(s.parse::<usize>().map_err(|e| format!("line {}: bad start: {}", n + 1, e))?),
+        };
+        let prerequisite = match fields[8] {
+            "." => None,
+            s => Some(parse_prerequisite(s).ok_or_else(|| format!("line {}: bad prerequisite '{}'", n + 1, s))?),
+        };
+
+        absences.push(PredictedAbsence {
+            trna_id: fields[4].to_string(),
+            seq_name: fields[0].to_string(),
+            genomic_position,
+            strand: Strand::from(fields[3].chars().next().unwrap_or('+')),
+            position: SprinzlPosition::new(fields[5]),
+            modification: fields[6].to_string(),
+            enzyme: fields[7].to_string(),
+            prerequisite,
+        });
+    }
+
+    Ok(absences)
+}
+
+/// Split "Cm32" into modification and Sprinzl position
+///
+/// The position is the trailing label: digits with an optional insertion
+/// letter ("17a"), or an extra-arm label ("e12").
+fn parse_prerequisite(s: &str) -> Option<Prerequisite> {
+    let unlettered = s.strip_suffix(|c: char| c.is_ascii_lowercase()).unwrap_or(s);
+    let digits = unlettered.trim_end_matches(|c: char| c.is_ascii_digit());
+    if digits.len() == unlettered.len() {
+        return None;
+    }
+    let split = match digits.strip_suffix('e') {
+        Some(name) if !name.is_empty() && unlettered.len() == s.len() => name.len(),
+        _ => digits.len(),
+    };
+    let (modification, position) = s.split_at(split);
+    if modification.is_empty() {
+        return None;
+    }
+    Some(Prerequisite {
+        position: SprinzlPosition::new(position),
+        modification: modification.to_string(),
+    })
+}
+
+/// Agreement between a predicted-absent site and modkit calls from the mutant
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AbsenceStatus {
+    /// Covered, and no call reaches the frequency threshold
+    Confirmed,
+    /// A modification is still called: compensation or a missing enzyme link
+    Unexpected,
+    /// No modkit record at the site
+    NoCoverage,
+}
+
+/// A predicted-absent site checked against modkit calls
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AbsenceCheck {
+    pub absence: PredictedAbsence,
+    pub status: AbsenceStatus,
+    /// Highest modified fraction among calls at the site
+    pub max_frequency: Option<f64>,
+    /// modkit codes called at the site
+    pub mod_codes: Vec<String>,
+}
+
+impl AbsenceCheck {
+    /// Check predicted absences against bedMethyl records
+    ///
+    /// A site counts as still modified when any call there has a modified
+    /// fraction of at least `min_frequency` (0-1; modkit reports percent).
+    pub fn check_all(
+        absences: &[PredictedAbsence],
+        records: &[BedMethylRecord],
+        min_frequency: f64,
+    ) -> Vec<AbsenceCheck> {
+        absences
+            .iter()
+            .map(|absence| {
+                let strand = match absence.strand {
+                    Strand::Plus => '+',
+                    Strand::Minus => '-',
+                };
+                let calls: Vec<&BedMethylRecord> = match absence.genomic_position {
+                    Some(pos) => records
+                        .iter()
+                        .filter(|r| {
+                            r.chrom == absence.seq_name
+                                && r.start == pos
+                                && (r.strand == strand || r.strand == '.')
+                        })
+                        .collect(),
+                    None => Vec::new(),
+                };
+
+                let max_frequency = calls
+                    .iter()
+                    .map(|r| r.mod_frequency / 100.0)
+                    .fold(None, |max: Option<f64>, f| Some(max.map_or(f, |m| m.max(f))));
+                let status = match max_frequency {
+                    None => AbsenceStatus::NoCoverage,
+                    Some(f) if f >= min_frequency => AbsenceStatus::Unexpected,
+                    Some(_) => AbsenceStatus::Confirmed,
+                };
+
+                AbsenceCheck {
+                    absence: absence.clone(),
+                    status,
+                    max_frequency,
+                    mod_codes: calls.iter().map(|r| r.mod_code.clone()).collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_support::{arm_options, phe_hit};
+
+    #[test]
+    fn test_knockout_direct_and_downstream_losses() {
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+        let trm7 = db.find_enzyme("TRM7").unwrap();
+
+        let absences = simulate_knockout(&[phe_hit("tRNA-Phe-GAA", Some("Phe"), &[])], &db, &options, &[trm7]);
+        let lost: Vec<String> = absences.iter()
+            .map(|a| format!("{}{}", a.modification, a.position))
+            .collect();
+        assert_eq!(lost, ["Cm32", "Gm34", "yW37"]);
+
+        let yw = &absences[2];
+        assert_eq!(yw.enzyme, "Trm7");
+        assert_eq!(yw.prerequisite.as_ref().map(|p| p.to_string()).as_deref(), Some("Cm32"));
+        // Position 37 is the 37th base of a plus-strand hit starting at 1001
+        assert_eq!(yw.genomic_position, Some(1036));
+    }
+
+    #[test]
+    fn test_absence_table_round_trip_and_check() {
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+        let trm2 = db.find_enzyme("trm2").unwrap();
+        let absences = simulate_knockout(&[phe_hit("tRNA-Phe-GAA", Some("Phe"), &[])], &db, &options, &[trm2]);
+        assert_eq!(absences.len(), 1);
+
+        let parsed = parse_absence_table(&write_absence_table(&absences)).unwrap();
+        assert_eq!(parsed, absences);
+
+        // Downstream losses keep their prerequisite (yW37 needs Cm32)
+        let trm7 = db.find_enzyme("trm7").unwrap();
+        let downstream = simulate_knockout(&[phe_hit("tRNA-Phe-GAA", Some("Phe"), &[])], &db, &options, &[trm7]);
+        assert!(downstream.iter().any(|a| a.prerequisite.is_some()));
+        assert_eq!(parse_absence_table(&write_absence_table(&downstream)).unwrap(), downstream);
+        let label = |s: &str| parse_prerequisite(s).map(|p| (p.modification, p.position.0));
+        assert_eq!(label("Psi55"), Some(("Psi".to_string(), "55".to_string())));
+        assert_eq!(label("m1A17a"), Some(("m1A".to_string(), "17a".to_string())));
+        assert_eq!(label("yWe12"), Some(("yW".to_string(), "e12".to_string())));
+        assert_eq!(label("Cm"), None);
+
+        let record = |freq: f64| BedMethylRecord {
+            chrom: "chr1".to_string(),
+            start: absences[0].genomic_position.unwrap(),
+            end: absences[0].genomic_position.unwrap() + 1,
+            mod_code: "m5U".to_string(),
+            score: 30,
+            strand: '+',
+            coverage: 30,
+            mod_frequency: freq,
+        };
+        let checks = AbsenceCheck::check_all(&absences, &[record(2.0)], 0.1);
+        assert_eq!(checks[0].status, AbsenceStatus::Confirmed);
+        let checks = AbsenceCheck::check_all(&absences, &[record(85.0)], 0.1);
+        assert_eq!(checks[0].status, AbsenceStatus::Unexpected);
+        let checks = AbsenceCheck::check_all(&absences, &[], 0.1);
+        assert_eq!(checks[0].status, AbsenceStatus::NoCoverage);
+    }
+}
@@ -3,10 +3,19 @@
 //! Modification compatibility analysis and odd tRNA detection.
 
 pub mod compatibility;
+pub mod fasta;
+pub mod genetic_code;
+pub mod knockout;
 pub mod odd_trna;
+pub mod per_position;
+pub mod policy;
+pub mod scoring;
+#[cfg(test)]
+pub(crate) mod test_support;
 
 use serde::{Deserialize, Serialize};
 use crate::SprinzlPosition;
+use crate::modification::SprinzlMapper;
 
 /// Represents a tRNA hit with associated metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +32,24 @@ pub struct TRNAHit {
     pub structure: String,
 }
 
+impl TRNAHit {
+    /// 0-based genomic coordinate of a base in the hit sequence
+    ///
+    /// Hit coordinates are 1-based and inclusive; minus-strand sequences run
+    /// from the higher coordinate down.
+    pub fn genomic_position(&self, seq_idx: usize) -> Option<usize> {
+        let (low, high) = (self.start.min(self.end), self.start.max(self.end));
+        let pos = match self.strand {
+            Strand::Plus => low.checked_add(seq_idx)?,
+            Strand::Minus => high.checked_sub(seq_idx)?,
+        };
+        if pos < low || pos > high {
+            return None;
+        }
+        pos.checked_sub(1)
+    }
+}
+
 /// Strand orientation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Strand {
@@ -39,23 +66,303 @@ impl From<char> for Strand {
     }
 }
 
+/// Where a tRNA sequence comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceSource {
+    /// Genome or gene model: modifications are invisible
+    #[default]
+    Genomic,
+    /// Derived from the RNA (tRNA-seq or nanopore consensus): modifications
+    /// may show as their sequencing signature
+    Transcript,
+}
+
+impl std::fmt::Display for SequenceSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceSource::Genomic => write!(f, "genomic"),
+            SequenceSource::Transcript => write!(f, "transcript"),
+        }
+    }
+}
+
+/// How the bases of a tRNA sequence are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceAlphabet {
+    /// Plain bases, with IUPAC ambiguity codes
+    #[default]
+    Iupac,
+    /// The MODOMICS alphabet: modified residues written as their one-character
+    /// codes ("P" or "Ψ" for pseudouridine, "D" for dihydrouridine, ...)
+    Modomics,
+}
+
+/// Options controlling how tRNA hits are analyzed
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptions {
+    /// Number Sprinzl positions from structure arms (armless mitochondrial tRNAs)
+    structure_aware_mapping: bool,
+    /// Genetic code used to call isotypes of hits without one
+    genetic_code: Option<GeneticCode>,
+    /// Priors and weights for the log-likelihood score
+    scoring: ScoringModel,
+    /// What makes a tRNA odd and how severe each lost site is
+    policy: OddPolicy,
+    /// Whether sequences are genomic or transcript-derived
+    source: SequenceSource,
+    /// How sequence characters are read
+    alphabet: SequenceAlphabet,
+}
+
+impl AnalysisOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options for mitochondrial tRNAs: arm-based numbering and the vertebrate mito code
+    pub fn mitochondrial() -> Self {
+        Self::new()
+            .with_structure_aware_mapping(true)
+            .with_genetic_code(GeneticCode::VertebrateMitochondrial)
+    }
+
+    /// Number Sprinzl positions from the hit's structure arms
+    pub fn with_structure_aware_mapping(mut self, enabled: bool) -> Self {
+        self.structure_aware_mapping = enabled;
+        self
+    }
+
+    /// Call isotypes from anticodons with this genetic code when a hit has none
+    pub fn with_genetic_code(mut self, code: GeneticCode) -> Self {
+        self.genetic_code = Some(code);
+        self
+    }
+
+    /// Score lost sites with this model instead of the default priors
+    pub fn with_scoring_model(mut self, model: ScoringModel) -> Self {
+        self.scoring = model;
+        self
+    }
+
+    pub fn scoring_model(&self) -> &ScoringModel {
+        &self.scoring
+    }
+
+    /// Call odd tRNAs and site severities with this policy instead of the default
+    pub fn with_odd_policy(mut self, policy: OddPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn odd_policy(&self) -> &OddPolicy {
+        &self.policy
+    }
+
+    /// Read sequences as coming from this source; transcript-derived sequences
+    /// take a signature base at an expected site as evidence of its modification
+    pub fn with_sequence_source(mut self, source: SequenceSource) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn sequence_source(&self) -> SequenceSource {
+        self.source
+    }
+
+    /// Read sequences in this alphabet; modification-annotated sequences are
+    /// analyzed on their parent bases and report the modifications they show
+    pub fn with_alphabet(mut self, alphabet: SequenceAlphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    pub fn alphabet(&self) -> SequenceAlphabet {
+        self.alphabet
+    }
+
+    /// The Sprinzl mapper for these options
+    pub fn sprinzl_mapper(&self) -> SprinzlMapper {
+        if self.structure_aware_mapping {
+            SprinzlMapper::new_mitochondrial()
+        } else {
+            SprinzlMapper::new_standard()
+        }
+    }
+
+    /// The isotype of a hit: its own annotation, or a call from its anticodon
+    pub fn isotype_for(&self, hit: &TRNAHit) -> Option<String> {
+        if let Some(isotype) = &hit.isotype {
+            return Some(isotype.clone());
+        }
+        let code = self.genetic_code?;
+        let anticodon = hit.anticodon.as_deref()?;
+        code.isotype_for_anticodon(anticodon).map(str::to_string)
+    }
+}
+
 /// Result of modification compatibility analysis for a tRNA
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModCompatibilityResult {
+    /// The analyzed hit; annotated sequences are replaced by their parent bases
     pub hit: TRNAHit,
+    /// The input sequence in the MODOMICS alphabet, when it was annotated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotated_sequence: Option<String>,
     pub sprinzl_alignment: std::collections::HashMap<SprinzlPosition, usize>,
     pub incompatibilities: Vec<ModificationIncompatibility>,
+    /// Whether the tRNA is odd under the analysis' [`OddPolicy`]
     pub is_odd: bool,
+    /// Legacy metric: compatible share of the compatible and incompatible positions
     pub compatibility_score: f64,
+    /// Sum of the lost sites' contributions (0 = nothing lost; lower is odder)
+    #[serde(default)]
+    pub log_likelihood: f64,
+    /// Cost of each lost position, in 5'-3' order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributions: Vec<PositionContribution>,
+    /// Expected positions whose base is N or not a nucleotide code
+    #[serde(default)]
+    pub not_evaluable: usize,
+    /// One verdict per position with expected modifications, in 5'-3' order
+    #[serde(default)]
+    pub verdicts: Vec<PositionVerdict>,
+}
+
+/// Outcome of checking the expected modifications at one position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    /// Every applicable expectation has an alternative that can be present
+    Compatible,
+    /// An applicable expectation has every alternative ruled out
+    Incompatible,
+    /// Only expectations applied for want of an isotype call are ruled out, or
+    /// an ambiguity code (R, Y, ...) leaves an expectation partly compatible
+    Ambiguous,
+    /// The base is N or not a nucleotide code, so nothing can be checked
+    NotEvaluable,
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Compatible => write!(f, "compatible"),
+            Verdict::Incompatible => write!(f, "incompatible"),
+            Verdict::Ambiguous => write!(f, "ambiguous"),
+            Verdict::NotEvaluable => write!(f, "not_evaluable"),
+        }
+    }
+}
+
+/// An expected alternative that cannot be present, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuledOut {
+    pub modification: String,
+    pub kind: IncompatibilityKind,
+}
+
+/// The verdict on one Sprinzl position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionVerdict {
+    pub position: SprinzlPosition,
+    /// `None` for N or a non-IUPAC symbol
+    pub observed_base: Option<crate::IupacBase>,
+    pub verdict: Verdict,
+    /// Expected alternatives that can be present
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ruled_in: Vec<String>,
+    /// Expected alternatives some of the bases an ambiguity code allows can
+    /// carry (all of them at a not-evaluable site)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partial: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ruled_out: Vec<RuledOut>,
+    /// Modification whose sequencing signature the observed base is, read as
+    /// its parent base (transcript-derived sequences only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Whether an expected edited site shows the base before or after editing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editing: Option<EditState>,
+    /// What the sequence shows at the site (modification-annotated input only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub observed: Option<SiteObservation>,
+    /// Most severe incompatibility at the position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
 }
 
 /// A specific modification incompatibility found at a position
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModificationIncompatibility {
     pub position: SprinzlPosition,
-    pub observed_base: crate::RnaBase,
+    pub observed_base: crate::IupacBase,
     pub expected_mod_name: String,
     pub severity: Severity,
+    #[serde(default)]
+    pub kind: IncompatibilityKind,
+    /// The required context the tRNA has lost (for `LostContext`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lost_context: Option<crate::modification::ContextPredicate>,
+    /// Enzymes whose substrate site is lost
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enzymes: Vec<String>,
+    /// The lost prerequisite modification (for `DependencyLoss`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prerequisite: Option<crate::modification::Prerequisite>,
+    /// Literature support for the expectation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<crate::modification::Evidence>,
+}
+
+/// Why an expected modification cannot be present
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IncompatibilityKind {
+    /// The base at the site cannot carry the modification
+    #[default]
+    BaseMismatch,
+    /// The site is intact but a base the modifying enzyme recognizes is not
+    LostContext,
+    /// The site is intact but a modification it builds on is lost elsewhere
+    DependencyLoss,
+    /// An edited site carries neither the base edited nor the edited base
+    NotEditable,
+}
+
+/// What a modification-annotated sequence shows at an expected site
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SiteObservation {
+    /// One of the expected modifications
+    Expected { modification: String },
+    /// A modification other than those expected
+    Different { modification: String },
+    /// The plain base
+    Unmodified,
+}
+
+impl SiteObservation {
+    /// The observed modification, if any
+    pub fn modification(&self) -> Option<&str> {
+        match self {
+            SiteObservation::Expected { modification } | SiteObservation::Different { modification } => {
+                Some(modification)
+            }
+            SiteObservation::Unmodified => None,
+        }
+    }
+}
+
+/// Where an edited site stands in the sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EditState {
+    /// The genomic base, which the RNA can edit
+    PreEditing,
+    /// The edited base, as in sequences taken after editing
+    PostEditing,
 }
 
 /// Severity of a modification incompatibility
@@ -66,5 +373,29 @@ pub enum Severity {
     Minor,
 }
 
-pub use compatibility::{analyze_compatibility, analyze_batch, BatchAnalysisResult};
+impl Severity {
+    fn rank(self) -> u8 {
+        match self {
+            Severity::Critical => 2,
+            Severity::Major => 1,
+            Severity::Minor => 0,
+        }
+    }
+
+    /// Whether this is `min` or more severe
+    pub fn at_least(self, min: Severity) -> bool {
+        self.rank() >= min.rank()
+    }
+}
+
+pub use compatibility::{
+    analyze_compatibility, analyze_compatibility_with_options, analyze_batch,
+    analyze_batch_with_options, BatchAnalysisResult, EnzymeLoss,
+};
+pub use fasta::{hit_from_alignment, parse_gtrnadb_name};
+pub use genetic_code::GeneticCode;
+pub use knockout::{simulate_knockout, AbsenceCheck, AbsenceStatus, PredictedAbsence};
 pub use odd_trna::detect_odd_trnas;
+pub use per_position::{position_rows, MappingConfidence, PositionRow};
+pub use policy::{OddPolicy, PolicyError};
+pub use scoring::{PositionContribution, ScoringModel};
@@ -1,16 +1,16 @@
 //! Odd tRNA detection
 
-use super::{TRNAHit, ModCompatibilityResult};
+use super::{TRNAHit, ModCompatibilityResult, AnalysisOptions};
 use crate::modification::ModificationDatabase;
 
-/// Detect odd tRNAs from a set of hits
+/// Detect odd tRNAs from a set of hits, as called by the options' odd policy
 pub fn detect_odd_trnas(
     hits: &[TRNAHit],
     mod_db: &ModificationDatabase,
-    threshold: f64,
+    options: &AnalysisOptions,
 ) -> Vec<ModCompatibilityResult> {
     hits.iter()
-        .map(|hit| super::analyze_compatibility(hit, mod_db))
-        .filter(|result| result.compatibility_score < threshold)
+        .map(|hit| super::analyze_compatibility_with_options(hit, mod_db, options))
+        .filter(|result| result.is_odd)
         .collect()
 }
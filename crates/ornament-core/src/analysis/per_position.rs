@@ -0,0 +1,176 @@
+//! Long-format per-position results
+//!
+//! Flattens an analyzed tRNA into one row per mapped Sprinzl position, in
+//! 5'-3' order, for tabular downstream analysis.
+
+use super::{ModCompatibilityResult, Severity, Strand, Verdict};
+use crate::modification::sprinzl::{pair_table, sprinzl_partner};
+use crate::modification::SprinzlMapper;
+use crate::SprinzlPosition;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How reliably a sequence base was assigned its Sprinzl number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingConfidence {
+    /// Stem position whose base pairs with its canonical partner in the structure
+    High,
+    /// Numbered from the structure, but in a loop or without the expected pair
+    Medium,
+    /// No structure: numbered by position in the sequence
+    Low,
+}
+
+impl fmt::Display for MappingConfidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MappingConfidence::High => write!(f, "high"),
+            MappingConfidence::Medium => write!(f, "medium"),
+            MappingConfidence::Low => write!(f, "low"),
+        }
+    }
+}
+
+/// One (tRNA, Sprinzl position) row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionRow {
+    pub trna_id: String,
+    pub seq_name: String,
+    pub strand: Strand,
+    pub position: SprinzlPosition,
+    /// 0-based index in the hit sequence
+    pub seq_index: usize,
+    /// 0-based genomic coordinate, if the hit has coordinates
+    pub genomic_position: Option<usize>,
+    pub observed_base: char,
+    /// Modification an annotated sequence shows at an expected site
+    pub observed_modification: Option<String>,
+    /// Expected alternatives: ruled in, partly compatible, then ruled out
+    /// (empty = nothing expected)
+    pub expected_modifications: Vec<String>,
+    /// `None` where nothing is expected
+    pub verdict: Option<Verdict>,
+    pub severity: Option<Severity>,
+    pub mapping_confidence: MappingConfidence,
+    /// Whether the tRNA as a whole is odd
+    pub is_odd: bool,
+}
+
+/// One row per mapped position of an analyzed tRNA, in 5'-3' order
+pub fn position_rows(result: &ModCompatibilityResult) -> Vec<PositionRow> {
+    let hit = &result.hit;
+    let sequence: Vec<char> = hit.sequence.chars().collect();
+    let pairs = (!hit.structure.is_empty() && hit.structure.chars().count() == sequence.len())
+        .then(|| pair_table(&hit.structure));
+
+    let confidence = |position: &SprinzlPosition, idx: usize| {
+        if hit.structure.is_empty() {
+            return MappingConfidence::Low;
+        }
+        let paired = sprinzl_partner(position)
+            .and_then(|partner| result.sprinzl_alignment.get(&partner))
+            .zip(pairs.as_ref().and_then(|p| p.get(idx).copied().flatten()))
+            .is_some_and(|(partner_idx, paired_idx)| *partner_idx == paired_idx);
+        if paired {
+            MappingConfidence::High
+        } else {
+            MappingConfidence::Medium
+        }
+    };
+
+    let mapper = SprinzlMapper::new_standard();
+    let mut mapped: Vec<(&SprinzlPosition, usize)> =
+        result.sprinzl_alignment.iter().map(|(p, idx)| (p, *idx)).collect();
+    mapped.sort_by_key(|(p, _)| mapper.sort_key(p));
+
+    mapped
+        .into_iter()
+        .map(|(position, idx)| {
+            let verdict = result.verdicts.iter().find(|v| &v.position == position);
+            PositionRow {
+                trna_id: hit.id.clone(),
+                seq_name: hit.seq_name.clone(),
+                strand: hit.strand,
+                position: position.clone(),
+                seq_index: idx,
+                genomic_position: hit.genomic_position(idx),
+                observed_base: sequence.get(idx).copied().unwrap_or('-'),
+                observed_modification: verdict
+                    .and_then(|v| v.observed.as_ref())
+                    .and_then(|o| o.modification())
+                    .map(str::to_string),
+                expected_modifications: verdict
+                    .map(|v| {
+                        let ruled_out = v.ruled_out.iter().map(|r| &r.modification);
+                        v.ruled_in.iter().chain(&v.partial).chain(ruled_out).cloned().collect()
+                    })
+                    .unwrap_or_default(),
+                verdict: verdict.map(|v| v.verdict),
+                severity: verdict.and_then(|v| v.severity),
+                mapping_confidence: confidence(position, idx),
+                is_odd: result.is_odd,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_compatibility_with_options;
+    use crate::analysis::test_support::{arm_options, phe_hit};
+    use crate::modification::ModificationDatabase;
+    use crate::output::{to_per_position_jsonl, to_per_position_tsv, PER_POSITION_COLUMNS};
+
+    #[test]
+    fn test_position_rows() {
+        let hit = phe_hit("tRNA-Phe", Some("Phe"), &[]);
+        let db = ModificationDatabase::eukaryotic();
+        let result = analyze_compatibility_with_options(&hit, &db, &arm_options());
+
+        let rows = position_rows(&result);
+        assert_eq!(rows.len(), result.sprinzl_alignment.len());
+        assert_eq!(rows[0].position.0, "1");
+        assert_eq!(rows[0].genomic_position, Some(1000));
+        assert_eq!(rows[0].mapping_confidence, MappingConfidence::High);
+
+        let psi55 = rows.iter().find(|r| r.position.0 == "55").unwrap();
+        assert_eq!(psi55.observed_base, 'U');
+        assert_eq!(psi55.expected_modifications, ["Psi"]);
+        assert_eq!(psi55.verdict, Some(Verdict::Compatible));
+        assert_eq!(psi55.mapping_confidence, MappingConfidence::Medium);
+        assert!(rows.iter().any(|r| r.verdict.is_none()));
+
+        // Same rows in both formats, in a stable order
+        let tsv = to_per_position_tsv(std::slice::from_ref(&result));
+        assert_eq!(tsv, to_per_position_tsv(std::slice::from_ref(&result)));
+        let lines: Vec<&str> = tsv.lines().collect();
+        assert_eq!(lines[0], PER_POSITION_COLUMNS.join("\t"));
+        assert_eq!(lines.len(), rows.len() + 1);
+        assert!(lines.iter().any(|l| l.starts_with("tRNA-Phe\tchr1\t+\t55\t") && l.contains("\tPsi\tcompatible\t")));
+
+        let jsonl = to_per_position_jsonl(std::slice::from_ref(&result)).unwrap();
+        let first: serde_json::Value = serde_json::from_str(jsonl.lines().next().unwrap()).unwrap();
+        assert_eq!(first["position"], "1");
+        assert_eq!(first["is_odd"], result.is_odd);
+    }
+
+    #[test]
+    fn test_ambiguous_rows() {
+        // Y (C or U) at Psi55 and N at m1A58 still name what is expected there
+        let hit = phe_hit("tRNA-Phe", Some("Phe"), &[(54, 'Y'), (57, 'N')]);
+        let db = ModificationDatabase::eukaryotic();
+        let result = analyze_compatibility_with_options(&hit, &db, &arm_options());
+        let rows = position_rows(&result);
+
+        let row = |position: &str| rows.iter().find(|r| r.position.0 == position).unwrap();
+        assert_eq!(row("55").verdict, Some(Verdict::Ambiguous));
+        assert_eq!(row("55").expected_modifications, ["Psi"]);
+        assert_eq!(row("58").verdict, Some(Verdict::NotEvaluable));
+        assert_eq!(row("58").expected_modifications, ["m1A"]);
+
+        let tsv = to_per_position_tsv(std::slice::from_ref(&result));
+        assert!(tsv.lines().any(|l| l.starts_with("tRNA-Phe\tchr1\t+\t55\t") && l.contains("\tPsi\tambiguous\t")));
+    }
+}
@@ -0,0 +1,284 @@
+//! Odd-calling policy
+//!
+//! One definition of an "odd" tRNA, shared by the library and every CLI
+//! subcommand: a score threshold, a minimum severity, and the rules that give
+//! each lost site its severity. The default policy is a bundled file
+//! (`data/odd_policy.toml`) in the same format users load with `--policy`.
+
+use super::{ModCompatibilityResult, Severity};
+use crate::modification::PositionModExpectation;
+use crate::{ConservationLevel, FunctionalRole, SprinzlPosition};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Current version of the policy file schema
+pub const ODD_POLICY_SCHEMA_VERSION: u32 = 1;
+
+const DEFAULT_POLICY: &str = include_str!("../../data/odd_policy.toml");
+
+/// When a tRNA is odd and how severe each lost site is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OddPolicy {
+    pub schema_version: u32,
+    /// Odd tRNAs score below this (legacy fraction-of-positions score)
+    pub score_threshold: f64,
+    /// Odd tRNAs have at least one lost site this severe
+    pub min_severity: Severity,
+    /// When set, odd tRNAs also have a log-likelihood below this
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_log_likelihood: Option<f64>,
+    pub conservation: ConservationSeverities,
+    #[serde(default)]
+    pub functional_role: RoleSeverities,
+    pub frequency: FrequencyCutoffs,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub positions: Vec<PositionOverride>,
+}
+
+/// Severity of a lost site by conservation level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ConservationSeverities {
+    pub universal: Severity,
+    pub domain_specific: Severity,
+    pub isotype_specific: Severity,
+    pub rare: Severity,
+}
+
+/// Severity overrides by functional role
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RoleSeverities {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anticodon_function: Option<Severity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structural_stability: Option<Severity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aminoacylation_identity: Option<Severity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unknown: Option<Severity>,
+}
+
+/// Frequency cutoffs for learned expectations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrequencyCutoffs {
+    pub critical: f64,
+    pub major: f64,
+}
+
+/// A fixed severity for one position, optionally for one modification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionOverride {
+    pub position: SprinzlPosition,
+    /// Short name of the modification (all modifications when absent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modification: Option<String>,
+    pub severity: Severity,
+}
+
+impl Default for OddPolicy {
+    fn default() -> Self {
+        Self::from_toml_str(DEFAULT_POLICY).expect("bundled odd policy is valid TOML")
+    }
+}
+
+impl OddPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_score_threshold(mut self, threshold: f64) -> Self {
+        self.score_threshold = threshold;
+        self
+    }
+
+    pub fn with_min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = severity;
+        self
+    }
+
+    pub fn with_max_log_likelihood(mut self, max: f64) -> Self {
+        self.max_log_likelihood = Some(max);
+        self
+    }
+
+    /// Fix the severity of a position (and optionally one modification there)
+    pub fn with_position_override(mut self, position: SprinzlPosition, modification: Option<String>, severity: Severity) -> Self {
+        self.positions.push(PositionOverride { position, modification, severity });
+        self
+    }
+
+    /// Read a policy file; `.json` files are parsed as JSON, everything else as TOML
+    pub fn from_path(path: &Path) -> Result<Self, PolicyError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| PolicyError::IoError(format!("{}: {}", path.display(), e)))?;
+        let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        if is_json {
+            Self::from_json_str(&content)
+        } else {
+            Self::from_toml_str(&content)
+        }
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self, PolicyError> {
+        let policy: Self = toml::from_str(s).map_err(|e| PolicyError::ParseError(e.to_string()))?;
+        policy.check()?;
+        Ok(policy)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self, PolicyError> {
+        let policy: Self = serde_json::from_str(s).map_err(|e| PolicyError::ParseError(e.to_string()))?;
+        policy.check()?;
+        Ok(policy)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, PolicyError> {
+        toml::to_string_pretty(self).map_err(|e| PolicyError::ParseError(e.to_string()))
+    }
+
+    fn check(&self) -> Result<(), PolicyError> {
+        if self.schema_version == 0 || self.schema_version > ODD_POLICY_SCHEMA_VERSION {
+            return Err(PolicyError::UnsupportedVersion(self.schema_version));
+        }
+        let mut problems = Vec::new();
+        for (name, value) in [("frequency.critical", self.frequency.critical), ("frequency.major", self.frequency.major)] {
+            if !(0.0..=1.0).contains(&value) {
+                problems.push(format!("{} must be between 0 and 1", name));
+            }
+        }
+        if self.frequency.major > self.frequency.critical {
+            problems.push("frequency.major must not exceed frequency.critical".to_string());
+        }
+        if !self.score_threshold.is_finite() {
+            problems.push("score_threshold must be a number".to_string());
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(PolicyError::Invalid(problems))
+        }
+    }
+
+    /// Severity of losing one of an expectation's modifications
+    pub fn severity_for(&self, expectation: &PositionModExpectation, modification: &str) -> Severity {
+        let position_override = self.positions.iter().find(|o| {
+            o.position == expectation.position
+                && o.modification.as_deref().is_none_or(|m| m == modification)
+        });
+        if let Some(o) = position_override {
+            return o.severity;
+        }
+        if let Some(severity) = self.functional_role.get(expectation.functional_role) {
+            return severity;
+        }
+        if let Some(frequency) = expectation.frequency {
+            return if frequency >= self.frequency.critical {
+                Severity::Critical
+            } else if frequency >= self.frequency.major {
+                Severity::Major
+            } else {
+                Severity::Minor
+            };
+        }
+        self.conservation.get(expectation.conservation)
+    }
+
+    /// Whether an analyzed tRNA is odd
+    pub fn is_odd(&self, result: &ModCompatibilityResult) -> bool {
+        result.compatibility_score < self.score_threshold
+            && result.incompatibilities.iter().any(|i| i.severity.at_least(self.min_severity))
+            && self.max_log_likelihood.is_none_or(|max| result.log_likelihood < max)
+    }
+}
+
+impl ConservationSeverities {
+    pub fn get(&self, level: ConservationLevel) -> Severity {
+        match level {
+            ConservationLevel::Universal => self.universal,
+            ConservationLevel::DomainSpecific => self.domain_specific,
+            ConservationLevel::IsotypeSpecific => self.isotype_specific,
+            ConservationLevel::Rare => self.rare,
+        }
+    }
+}
+
+impl RoleSeverities {
+    pub fn get(&self, role: FunctionalRole) -> Option<Severity> {
+        match role {
+            FunctionalRole::AnticodonFunction => self.anticodon_function,
+            FunctionalRole::StructuralStability => self.structural_stability,
+            FunctionalRole::AminoacylationIdentity => self.aminoacylation_identity,
+            FunctionalRole::Unknown => self.unknown,
+        }
+    }
+}
+
+/// Errors from loading a policy file
+#[derive(Debug)]
+pub enum PolicyError {
+    IoError(String),
+    ParseError(String),
+    UnsupportedVersion(u32),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolicyError::IoError(e) => write!(f, "IO error: {}", e),
+            PolicyError::ParseError(e) => write!(f, "Parse error: {}", e),
+            PolicyError::UnsupportedVersion(v) => write!(
+                f,
+                "Unsupported odd policy schema version {} (this build reads up to {})",
+                v, ODD_POLICY_SCHEMA_VERSION
+            ),
+            PolicyError::Invalid(problems) => {
+                write!(f, "Invalid odd policy:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_compatibility_with_options;
+    use crate::analysis::test_support::{arm_options, phe_hit};
+    use crate::modification::ModificationDatabase;
+
+    #[test]
+    fn test_policy_overrides() {
+        // tRNA-Phe with A32: Cm32 is lost (Major by default) and yW37 with it;
+        // arm-based numbering also puts G at 20, losing D20
+        let hit = phe_hit("Phe-A32", Some("Phe"), &[(31, 'A')]);
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+
+        let result = analyze_compatibility_with_options(&hit, &db, &options);
+        assert!(result.is_odd);
+
+        // Demoting every lost site to Minor means it is no longer odd
+        let mut toml = DEFAULT_POLICY.to_string();
+        for position in ["20", "32", "37"] {
+            toml.push_str(&format!("\n[[positions]]\nposition = \"{}\"\nseverity = \"Minor\"\n", position));
+        }
+        let policy = OddPolicy::from_toml_str(&toml).unwrap();
+        let options = options.with_odd_policy(policy.clone());
+        let result = analyze_compatibility_with_options(&hit, &db, &options);
+        let cm = result.incompatibilities.iter().find(|i| i.position.0 == "32").unwrap();
+        assert_eq!(cm.severity, Severity::Minor);
+        assert!(!result.is_odd);
+        assert!(policy.clone().with_min_severity(Severity::Minor).is_odd(&result));
+
+        // Bad values are reported together
+        let bad = DEFAULT_POLICY.replace("critical = 0.9", "critical = 1.5");
+        assert!(matches!(OddPolicy::from_toml_str(&bad), Err(PolicyError::Invalid(p)) if p.len() == 1));
+    }
+}
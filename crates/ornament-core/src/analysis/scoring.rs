@@ -0,0 +1,195 @@
+//! Probabilistic compatibility scoring
+//!
+//! Each expected site has a probability `p` of carrying its modification in a
+//! normal tRNA: the learned frequency when the expectation has one, otherwise
+//! a prior for its conservation level. Losing the site contributes
+//! `weight * ln(1 - p)`, where the weight reflects its functional role, so
+//! losing Psi55 costs far more than losing a rare site. A tRNA's log-likelihood
+//! is the sum over lost sites (0 when nothing is lost); lower is odder.
+
+use super::ModificationIncompatibility;
+use crate::modification::{ModificationDatabase, PositionModExpectation};
+use crate::{ConservationLevel, FunctionalRole, SprinzlPosition};
+use serde::{Deserialize, Serialize};
+
+/// Probabilities are kept away from 0 and 1 so every loss has a finite cost
+const MIN_PROBABILITY: f64 = 0.001;
+const MAX_PROBABILITY: f64 = 0.999;
+
+/// Priors and weights turning lost sites into a log-likelihood
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoringModel {
+    /// Probability a site is modified, by conservation level
+    universal: f64,
+    domain_specific: f64,
+    isotype_specific: f64,
+    rare: f64,
+    /// Weight of a loss, by functional role
+    anticodon_function: f64,
+    structural_stability: f64,
+    aminoacylation_identity: f64,
+    unknown_role: f64,
+}
+
+impl Default for ScoringModel {
+    fn default() -> Self {
+        Self {
+            universal: 0.95,
+            domain_specific: 0.8,
+            isotype_specific: 0.8,
+            rare: 0.2,
+            anticodon_function: 1.5,
+            structural_stability: 1.0,
+            aminoacylation_identity: 1.5,
+            unknown_role: 1.0,
+        }
+    }
+}
+
+impl ScoringModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the probability a site of this conservation level is modified
+    pub fn with_prior(mut self, level: ConservationLevel, probability: f64) -> Self {
+        *match level {
+            ConservationLevel::Universal => &mut self.universal,
+            ConservationLevel::DomainSpecific => &mut self.domain_specific,
+            ConservationLevel::IsotypeSpecific => &mut self.isotype_specific,
+            ConservationLevel::Rare => &mut self.rare,
+        } = probability;
+        self
+    }
+
+    /// Set the weight of losing a site with this functional role
+    pub fn with_role_weight(mut self, role: FunctionalRole, weight: f64) -> Self {
+        *match role {
+            FunctionalRole::AnticodonFunction => &mut self.anticodon_function,
+            FunctionalRole::StructuralStability => &mut self.structural_stability,
+            FunctionalRole::AminoacylationIdentity => &mut self.aminoacylation_identity,
+            FunctionalRole::Unknown => &mut self.unknown_role,
+        } = weight;
+        self
+    }
+
+    pub fn prior(&self, level: ConservationLevel) -> f64 {
+        match level {
+            ConservationLevel::Universal => self.universal,
+            ConservationLevel::DomainSpecific => self.domain_specific,
+            ConservationLevel::IsotypeSpecific => self.isotype_specific,
+            ConservationLevel::Rare => self.rare,
+        }
+    }
+
+    pub fn role_weight(&self, role: FunctionalRole) -> f64 {
+        match role {
+            FunctionalRole::AnticodonFunction => self.anticodon_function,
+            FunctionalRole::StructuralStability => self.structural_stability,
+            FunctionalRole::AminoacylationIdentity => self.aminoacylation_identity,
+            FunctionalRole::Unknown => self.unknown_role,
+        }
+    }
+
+    /// Probability the expected site is modified in a normal tRNA
+    pub fn probability(&self, expectation: &PositionModExpectation) -> f64 {
+        expectation
+            .frequency
+            .unwrap_or_else(|| self.prior(expectation.conservation))
+            .clamp(MIN_PROBABILITY, MAX_PROBABILITY)
+    }
+
+    /// Log-likelihood contribution of losing the expected site
+    pub fn loss_contribution(&self, expectation: &PositionModExpectation) -> f64 {
+        self.role_weight(expectation.functional_role) * (1.0 - self.probability(expectation)).ln()
+    }
+}
+
+/// The log-likelihood cost of a lost site
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionContribution {
+    pub position: SprinzlPosition,
+    /// The lost modification that sets the cost
+    pub modification: String,
+    pub probability: f64,
+    pub weight: f64,
+    /// `weight * ln(1 - probability)`; always negative
+    pub contribution: f64,
+}
+
+/// Log-likelihood and per-position contributions of a tRNA's lost sites
+///
+/// An expectation is lost when every one of its alternative modifications is
+/// reported lost at its position. A position counts once, at the cost of its
+/// most severe lost expectation.
+pub(crate) fn score_losses(
+    applicable: &[(&SprinzlPosition, crate::IupacBase, &PositionModExpectation)],
+    incompatibilities: &[ModificationIncompatibility],
+    mod_db: &ModificationDatabase,
+    model: &ScoringModel,
+) -> (f64, Vec<PositionContribution>) {
+    let is_lost = |position: &SprinzlPosition, name: &str| {
+        incompatibilities.iter().any(|i| {
+            &i.position == position && mod_db.same_modification(&i.expected_mod_name, name)
+        })
+    };
+
+    let mut contributions: Vec<PositionContribution> = Vec::new();
+    for (position, _, expectation) in applicable {
+        let names = expectation.names();
+        if names.is_empty() || !names.iter().all(|name| is_lost(position, name)) {
+            continue;
+        }
+        let contribution = PositionContribution {
+            position: (*position).clone(),
+            modification: names[0].clone(),
+            probability: model.probability(expectation),
+            weight: model.role_weight(expectation.functional_role),
+            contribution: model.loss_contribution(expectation),
+        };
+        match contributions.iter_mut().find(|c| &c.position == *position) {
+            Some(existing) if existing.contribution > contribution.contribution => *existing = contribution,
+            Some(_) => {}
+            None => contributions.push(contribution),
+        }
+    }
+
+    let mapper = crate::modification::SprinzlMapper::new_standard();
+    contributions.sort_by_key(|c| mapper.sort_key(&c.position));
+    let log_likelihood = contributions.iter().map(|c| c.contribution).sum();
+    (log_likelihood, contributions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_compatibility_with_options;
+    use crate::analysis::test_support::{arm_options, phe_hit};
+
+    #[test]
+    fn test_losses_weighted_by_conservation() {
+        let db = ModificationDatabase::eukaryotic();
+        let options = arm_options();
+        let intact = analyze_compatibility_with_options(&phe_hit("intact", Some("Phe"), &[]), &db, &options);
+        // C55 loses Psi55; A32 loses Cm32 (and yW37 with it)
+        let psi = analyze_compatibility_with_options(&phe_hit("psi55", Some("Phe"), &[(54, 'C')]), &db, &options);
+        let cm = analyze_compatibility_with_options(&phe_hit("cm32", Some("Phe"), &[(31, 'A')]), &db, &options);
+
+        let at = |result: &crate::analysis::ModCompatibilityResult, pos: &str| {
+            result.contributions.iter().find(|c| c.position.0 == pos).map(|c| c.contribution)
+        };
+        assert_eq!(at(&intact, "55"), None);
+        assert!(psi.log_likelihood < intact.log_likelihood);
+        let psi55 = at(&psi, "55").expect("Psi55 contribution");
+        let cm32 = at(&cm, "32").expect("Cm32 contribution");
+        assert!(psi55 < cm32, "losing Psi55 ({}) should cost more than Cm32 ({})", psi55, cm32);
+        assert!((psi.log_likelihood - psi.contributions.iter().map(|c| c.contribution).sum::<f64>()).abs() < 1e-12);
+
+        // A custom model changes the cost
+        let flat = options.clone().with_scoring_model(
+            ScoringModel::new().with_prior(ConservationLevel::Universal, 0.5).with_role_weight(FunctionalRole::StructuralStability, 1.0),
+        );
+        let flat_psi = analyze_compatibility_with_options(&phe_hit("psi55", Some("Phe"), &[(54, 'C')]), &db, &flat);
+        assert!(at(&flat_psi, "55").unwrap() > psi55);
+    }
+}
@@ -0,0 +1,35 @@
+//! Shared test fixtures: yeast tRNA-Phe and its variants
+
+use super::{AnalysisOptions, Strand, TRNAHit};
+
+/// Yeast tRNA-Phe (anticodon GAA at indices 33-35)
+pub const PHE_SEQUENCE: &str = "GCGGAUUUAGCUCAGUUGGGAGAGCGCCAGACUGAAGAUCUGGAGGUCCUGUGUUCGAUCCACAGAAUUCGCACCA";
+pub const PHE_STRUCTURE: &str = "(((((((..((((........)))).(((((.......))))).....(((((.......))))))))))))....";
+
+/// tRNA-Phe on chr1:1001-1076 with bases replaced at sequence indices
+///
+/// Under arm numbering ([`arm_options`]) index 31 is position 32, 33 is 34,
+/// 53 is 54, 54 is 55 and 57 is 58. The anticodon is read from the sequence.
+pub fn phe_hit(id: &str, isotype: Option<&str>, changes: &[(usize, char)]) -> TRNAHit {
+    let mut sequence: Vec<char> = PHE_SEQUENCE.chars().collect();
+    for &(idx, base) in changes {
+        sequence[idx] = base;
+    }
+    TRNAHit {
+        id: id.to_string(),
+        seq_name: "chr1".to_string(),
+        start: 1001,
+        end: 1076,
+        strand: Strand::Plus,
+        score: 80.0,
+        isotype: isotype.map(str::to_string),
+        anticodon: Some(sequence[33..36].iter().collect()),
+        sequence: sequence.into_iter().collect(),
+        structure: PHE_STRUCTURE.to_string(),
+    }
+}
+
+/// Options numbering positions from the structure's arms
+pub fn arm_options() -> AnalysisOptions {
+    AnalysisOptions::new().with_structure_aware_mapping(true)
+}
@@ -261,6 +261,7 @@ impl TopHits {
                     score: hit.score as f64,
                     e_value: hit.evalue,
                     gc_content: 0.0, // Not available directly from CM_HIT
+                    spans_origin: false,
                 });
             }
         }
@@ -8,4 +8,4 @@ pub mod parser;
 
 pub use ffi::{Alphabet, CovarianceModel, HmmFilter, Sequence, SequenceFile, TopHits};
 pub use runner::InfernalRunner;
-pub use parser::{CMHit, CMAlignment};
+pub use parser::{AlignedSequence, CMHit, CMAlignment};
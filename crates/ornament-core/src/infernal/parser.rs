@@ -1,8 +1,9 @@
 //! Infernal output parser
 //!
-//! Parses cmsearch tabular and Stockholm alignment outputs.
+//! Parses cmsearch tabular output and cmalign Stockholm alignments.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// A covariance model hit from cmsearch
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +16,9 @@ pub struct CMHit {
     pub score: f64,
     pub e_value: f64,
     pub gc_content: f64,
+    /// Hit wraps around the origin of a circular sequence (end coordinate is past the origin)
+    #[serde(default)]
+    pub spans_origin: bool,
 }
 
 /// Alignment from cmsearch Stockholm output
@@ -58,9 +62,169 @@ pub fn parse_tblout(content: &str) -> Vec<CMHit> {
                 score,
                 e_value,
                 gc_content: gc,
+                spans_origin: false,
             });
         }
     }
 
     hits
 }
+
+/// One sequence of a cmalign alignment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignedSequence {
+    pub name: String,
+    /// Aligned row: consensus residues uppercase, inserts lowercase, gaps '-' or '.'
+    pub aligned: String,
+    /// Consensus structure of the alignment columns (WUSS notation)
+    pub ss_cons: String,
+    /// Bit score of the alignment, if cmalign reported one
+    #[serde(default)]
+    pub score: Option<f64>,
+}
+
+impl AlignedSequence {
+    /// The sequence's own residues, uppercase RNA
+    pub fn sequence(&self) -> String {
+        self.aligned
+            .chars()
+            .filter(|c| !is_gap(*c))
+            .map(|c| match c.to_ascii_uppercase() {
+                'T' => 'U',
+                c => c,
+            })
+            .collect()
+    }
+
+    /// Dot-bracket structure of the sequence's own residues
+    ///
+    /// Consensus pairs are kept when both partners are present; pseudoknot
+    /// letters and pairs with a deleted partner are written unpaired.
+    pub fn structure(&self) -> String {
+        let columns: Vec<char> = self.aligned.chars().collect();
+        let partners = wuss_pairs(&self.ss_cons);
+        columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !is_gap(**c))
+            .map(|(col, _)| match partners.get(&col) {
+                Some(&partner) if columns.get(partner).is_some_and(|c| !is_gap(*c)) => {
+                    if partner > col { '(' } else { ')' }
+                }
+                _ => '.',
+            })
+            .collect()
+    }
+}
+
+fn is_gap(c: char) -> bool {
+    matches!(c, '-' | '.' | '_' | '~')
+}
+
+/// Column -> partner column for the bracket pairs of a WUSS structure
+fn wuss_pairs(ss_cons: &str) -> HashMap<usize, usize> {
+    let mut pairs = HashMap::new();
+    let mut stacks: HashMap<char, Vec<usize>> = HashMap::new();
+    for (col, c) in ss_cons.chars().enumerate() {
+        match c {
+            '<' | '(' | '[' | '{' => stacks.entry(c).or_default().push(col),
+            '>' | ')' | ']' | '}' => {
+                let open = match c {
+                    '>' => '<',
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if let Some(partner) = stacks.get_mut(&open).and_then(Vec::pop) {
+                    pairs.insert(col, partner);
+                    pairs.insert(partner, col);
+                }
+            }
+            _ => {}
+        }
+    }
+    pairs
+}
+
+/// Parse a Stockholm alignment written by cmalign
+///
+/// Interleaved blocks are joined; sequences keep their order of appearance.
+pub fn parse_stockholm(content: &str) -> Vec<AlignedSequence> {
+    let mut order: Vec<String> = Vec::new();
+    let mut rows: HashMap<String, String> = HashMap::new();
+    let mut ss_cons = String::new();
+
+    for line in content.lines() {
+        let line = line.trim_end();
+        if let Some(rest) = line.strip_prefix("#=GC SS_cons") {
+            ss_cons.push_str(rest.trim());
+            continue;
+        }
+        if line.is_empty() || line.starts_with('#') || line == "//" {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(name), Some(row)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        if !rows.contains_key(name) {
+            order.push(name.to_string());
+        }
+        rows.entry(name.to_string()).or_default().push_str(row);
+    }
+
+    order
+        .into_iter()
+        .map(|name| AlignedSequence {
+            aligned: rows.remove(&name).unwrap_or_default(),
+            name,
+            ss_cons: ss_cons.clone(),
+            score: None,
+        })
+        .collect()
+}
+
+/// Parse the bit scores of a cmalign score file (`--sfile`), by sequence name
+pub fn parse_cmalign_scores(content: &str) -> HashMap<String, f64> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            // seq idx, seq name, length, cm from, cm to, trunc, bit sc, avg pp, ...
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let score = fields.get(6)?.parse::<f64>().ok()?;
+            Some((fields[1].to_string(), score))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stockholm() {
+        // Two interleaved blocks; seq2 lacks the 3' partner of the first pair
+        // and carries an insert
+        let stockholm = "# STOCKHOLM 1.0\n\n\
+            seq1         GCG-AuUC\n\
+            seq2         GCGAA.U-\n\
+            #=GC SS_cons <<..>...\n\
+            \n\
+            seq1         GC\n\
+            seq2         G-\n\
+            #=GC SS_cons .>\n\
+            //\n";
+        let alignments = parse_stockholm(stockholm);
+        assert_eq!(alignments.len(), 2);
+        assert_eq!(alignments[0].name, "seq1");
+        assert_eq!(alignments[0].ss_cons, "<<..>....>");
+        assert_eq!(alignments[0].sequence(), "GCGAUUCGC");
+        assert_eq!(alignments[0].structure(), "((.)....)");
+        assert_eq!(alignments[1].sequence(), "GCGAAUG");
+        assert_eq!(alignments[1].structure(), ".(..)..");
+
+        let scores = parse_cmalign_scores("# header\n     1  seq1  9  1  8  no  42.5  0.98\n");
+        assert_eq!(scores.get("seq1"), Some(&42.5));
+    }
+}
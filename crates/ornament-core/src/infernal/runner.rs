@@ -1,13 +1,16 @@
 //! Infernal command runner
 //!
-//! Executes cmsearch as subprocess for tRNA detection.
+//! Executes cmsearch as subprocess for tRNA detection, and cmalign to align
+//! tRNA sequences to a model.
 
+use std::collections::HashMap;
+use std::io::Write;
 use std::path::Path;
 use std::process::Command;
 use anyhow::{anyhow, Result};
 
-use super::parser::parse_tblout;
-use super::CMHit;
+use super::parser::{parse_cmalign_scores, parse_stockholm, parse_tblout};
+use super::{AlignedSequence, CMHit};
 
 /// Runner for Infernal commands
 pub struct InfernalRunner {
@@ -16,14 +19,20 @@ pub struct InfernalRunner {
     e_value: f64,
     /// Number of CPUs to use
     cpu: usize,
+    /// Bases of the sequence start appended to each circular sequence (None = linear)
+    circular_overlap: Option<usize>,
 }
 
+/// Default overlap for circular sequences; longer than any tRNA gene including introns
+pub const DEFAULT_CIRCULAR_OVERLAP: usize = 200;
+
 impl InfernalRunner {
     pub fn new() -> Self {
         Self {
             cm_path: None,
             e_value: 1e-5,
             cpu: num_cpus(),
+            circular_overlap: None,
         }
     }
 
@@ -45,14 +54,58 @@ impl InfernalRunner {
         self
     }
 
+    /// Treat input sequences as circular (e.g. mitochondrial genomes)
+    ///
+    /// The start of each sequence is appended to its end before searching, so
+    /// genes spanning the origin are found whole. Their end coordinate is then
+    /// wrapped back past the origin and the hit is flagged with `spans_origin`.
+    pub fn with_circular(mut self, circular: bool) -> Self {
+        self.circular_overlap = circular.then_some(DEFAULT_CIRCULAR_OVERLAP);
+        self
+    }
+
     /// Run cmsearch on a FASTA file using subprocess
     pub fn cmsearch<P: AsRef<Path>>(&self, fasta: P) -> Result<Vec<CMHit>> {
+        match self.circular_overlap {
+            Some(overlap) => self.cmsearch_circular(fasta.as_ref(), overlap),
+            None => self.run_cmsearch(fasta.as_ref()),
+        }
+    }
+
+    /// Run cmsearch on circularized copies of the sequences in a FASTA file
+    fn cmsearch_circular(&self, fasta: &Path, overlap: usize) -> Result<Vec<CMHit>> {
+        if !fasta.exists() {
+            return Err(anyhow!("FASTA file not found: {}", fasta.display()));
+        }
+
+        let reader = bio::io::fasta::Reader::from_file(fasta)?;
+        let mut extended = tempfile::Builder::new().suffix(".fa").tempfile()?;
+        let mut lengths = HashMap::new();
+
+        for record in reader.records() {
+            let record = record?;
+            let seq = record.seq();
+            let ext = overlap.min(seq.len());
+
+            writeln!(extended, ">{}", record.id())?;
+            extended.write_all(seq)?;
+            extended.write_all(&seq[..ext])?;
+            writeln!(extended)?;
+
+            lengths.insert(record.id().to_string(), seq.len());
+        }
+        extended.flush()?;
+
+        let hits = self.run_cmsearch(extended.path())?;
+        Ok(unwrap_circular_hits(hits, &lengths))
+    }
+
+    fn run_cmsearch(&self, fasta_path: &Path) -> Result<Vec<CMHit>> {
         let cm_path = self
             .cm_path
             .as_ref()
             .ok_or_else(|| anyhow!("No covariance model specified"))?;
 
-        let fasta_path = fasta.as_ref();
         if !fasta_path.exists() {
             return Err(anyhow!("FASTA file not found: {}", fasta_path.display()));
         }
@@ -89,6 +142,54 @@ impl InfernalRunner {
 
         Ok(hits)
     }
+
+    /// Align every sequence of a FASTA file to the model with cmalign
+    ///
+    /// Sequences come back in input order, with their alignment bit scores.
+    pub fn cmalign<P: AsRef<Path>>(&self, fasta: P) -> Result<Vec<AlignedSequence>> {
+        let fasta_path = fasta.as_ref();
+        let cm_path = self
+            .cm_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("No covariance model specified"))?;
+
+        if !fasta_path.exists() {
+            return Err(anyhow!("FASTA file not found: {}", fasta_path.display()));
+        }
+
+        // Pfam format keeps each sequence on one line; scores go to a side file
+        let scores = tempfile::Builder::new().suffix(".sc").tempfile()?;
+        let output = Command::new("cmalign")
+            .arg("--outformat")
+            .arg("Pfam")
+            .arg("--sfile")
+            .arg(scores.path())
+            .arg("--cpu")
+            .arg(self.cpu.to_string())
+            .arg(cm_path)
+            .arg(fasta_path)
+            .output()
+            .map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    anyhow!("cmalign not found. Please install Infernal: http://eddylab.org/infernal/")
+                } else {
+                    anyhow!("Failed to run cmalign: {}", e)
+                }
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(anyhow!("cmalign failed: {}", stderr));
+        }
+
+        let mut alignments = parse_stockholm(&String::from_utf8_lossy(&output.stdout));
+        let scores = parse_cmalign_scores(&std::fs::read_to_string(scores.path())?);
+        for alignment in &mut alignments {
+            alignment.score = scores.get(&alignment.name).copied();
+        }
+
+        Ok(alignments)
+    }
 }
 
 impl Default for InfernalRunner {
@@ -97,6 +198,39 @@ impl Default for InfernalRunner {
     }
 }
 
+/// Map hits on circularized sequences back to original coordinates
+///
+/// Hits lying entirely in the appended copy of the sequence start duplicate a
+/// hit near the origin and are dropped; hits running past the original end
+/// have that coordinate wrapped and are marked as spanning the origin.
+pub fn unwrap_circular_hits(hits: Vec<CMHit>, lengths: &HashMap<String, usize>) -> Vec<CMHit> {
+    let mut result = Vec::with_capacity(hits.len());
+
+    for mut hit in hits {
+        let Some(&len) = lengths.get(&hit.target_name) else {
+            result.push(hit);
+            continue;
+        };
+
+        let lo = hit.target_start.min(hit.target_end);
+        let hi = hit.target_start.max(hit.target_end);
+        if lo > len {
+            continue;
+        }
+        if hi > len {
+            if hit.target_start > len {
+                hit.target_start -= len;
+            } else {
+                hit.target_end -= len;
+            }
+            hit.spans_origin = true;
+        }
+        result.push(hit);
+    }
+
+    result
+}
+
 /// Get number of available CPUs
 fn num_cpus() -> usize {
     std::thread::available_parallelism()
@@ -118,5 +252,36 @@ mod tests {
         assert_eq!(runner.cm_path, Some("test.cm".to_string()));
         assert_eq!(runner.e_value, 1e-10);
         assert_eq!(runner.cpu, 4);
+        assert_eq!(runner.circular_overlap, None);
+    }
+
+    #[test]
+    fn test_unwrap_circular_hits() {
+        let hit = |start: usize, end: usize, strand: char| CMHit {
+            target_name: "chrM".to_string(),
+            target_start: start,
+            target_end: end,
+            strand,
+            query_name: "tRNA".to_string(),
+            score: 50.0,
+            e_value: 1e-10,
+            gc_content: 0.4,
+            spans_origin: false,
+        };
+        let lengths: HashMap<String, usize> = [("chrM".to_string(), 16569)].into_iter().collect();
+
+        let hits = vec![
+            hit(100, 170, '+'),     // ordinary hit
+            hit(16560, 16620, '+'), // spans the origin
+            hit(16600, 16560, '-'), // spans the origin on the minus strand
+            hit(16669, 16739, '+'), // duplicate of the first hit in the appended copy
+        ];
+        let unwrapped = unwrap_circular_hits(hits, &lengths);
+
+        assert_eq!(unwrapped.len(), 3);
+        assert!(!unwrapped[0].spans_origin);
+        assert_eq!((unwrapped[1].target_start, unwrapped[1].target_end), (16560, 51));
+        assert!(unwrapped[1].spans_origin);
+        assert_eq!((unwrapped[2].target_start, unwrapped[2].target_end), (31, 16560));
     }
 }
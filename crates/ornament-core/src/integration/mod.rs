@@ -1,7 +1,9 @@
 //! Integration module
 //!
-//! Integration with external data sources like modkit.
+//! Integration with external data sources like modkit and the MODOMICS API.
 
 pub mod modkit;
+pub mod modomics_client;
 
 pub use modkit::BedMethylRecord;
+pub use modomics_client::{ClientError, Endpoint, FetchOutcome, FetchStatus, ModomicsClient};
@@ -0,0 +1,410 @@
+//! MODOMICS API client with an on-disk cache
+//!
+//! Each endpoint is cached as `<name>.json` with a `<name>.meta.json` sidecar
+//! holding the URL, ETag, Last-Modified and fetch timestamps. Updates send the
+//! cached validators, so an unchanged release costs a 304. A fresh body is
+//! validated before it replaces the cached copy, and when the server cannot
+//! be reached (or the client is offline) the cache is used as-is.
+
+use crate::modification::modomics::import_modomics_json;
+use crate::modification::observed::validate_sequence_json;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Public MODOMICS server
+pub const DEFAULT_BASE_URL: &str = "https://genesilico.pl/modomics";
+
+/// A cached API endpoint
+#[derive(Debug, Clone)]
+pub struct Endpoint {
+    /// Cache file stem (e.g. "modifications")
+    pub name: String,
+    /// Path and query relative to the base URL
+    pub path: String,
+    /// Checks a response body before it is cached
+    pub validate: fn(&str) -> Result<(), String>,
+}
+
+impl Endpoint {
+    /// Modification definitions, validated against the `ModomicsEntry` schema
+    pub fn modifications() -> Self {
+        Self {
+            name: "modifications".to_string(),
+            path: "api/modifications".to_string(),
+            validate: validate_modifications,
+        }
+    }
+
+    /// tRNA sequences with modification annotations
+    pub fn trna_sequences() -> Self {
+        Self {
+            name: "trna_sequences".to_string(),
+            path: "api/sequences?RNAtype=tRNA".to_string(),
+            validate: validate_sequences,
+        }
+    }
+
+    /// Both endpoints `db update` fetches
+    pub fn defaults() -> Vec<Self> {
+        vec![Self::modifications(), Self::trna_sequences()]
+    }
+}
+
+fn validate_modifications(body: &str) -> Result<(), String> {
+    let import = import_modomics_json(body).map_err(|e| e.to_string())?;
+    if import.modifications.is_empty() {
+        return Err("no usable modification entries".to_string());
+    }
+    Ok(())
+}
+
+fn validate_sequences(body: &str) -> Result<(), String> {
+    validate_sequence_json(body).map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Sidecar metadata of a cached response
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheMeta {
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+    /// When the cached body was downloaded (Unix seconds)
+    pub fetched_at: u64,
+    /// When the server last confirmed it (Unix seconds)
+    pub checked_at: u64,
+}
+
+/// What an update did for one endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FetchStatus {
+    /// A new body was downloaded, validated and cached
+    Updated,
+    /// The server confirmed the cached body is current
+    NotModified,
+    /// The cached body was used without contacting the server
+    Cached,
+}
+
+impl fmt::Display for FetchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchStatus::Updated => write!(f, "updated"),
+            FetchStatus::NotModified => write!(f, "not modified"),
+            FetchStatus::Cached => write!(f, "cached"),
+        }
+    }
+}
+
+/// Result of updating one endpoint
+#[derive(Debug, Clone)]
+pub struct FetchOutcome {
+    pub endpoint: String,
+    pub status: FetchStatus,
+    /// Cached body
+    pub path: PathBuf,
+    pub meta: CacheMeta,
+    /// Why the server was not used, when falling back to the cache
+    pub warning: Option<String>,
+}
+
+/// Fetches MODOMICS endpoints through the cache
+#[derive(Debug, Clone)]
+pub struct ModomicsClient {
+    base_url: String,
+    cache_dir: PathBuf,
+    offline: bool,
+    timeout: Duration,
+}
+
+impl ModomicsClient {
+    pub fn new(cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            cache_dir: cache_dir.into(),
+            offline: false,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Never contact the server; serve everything from the cache
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Cached body of an endpoint
+    pub fn cache_path(&self, endpoint: &Endpoint) -> PathBuf {
+        self.cache_dir.join(format!("{}.json", endpoint.name))
+    }
+
+    fn meta_path(&self, endpoint: &Endpoint) -> PathBuf {
+        self.cache_dir.join(format!("{}.meta.json", endpoint.name))
+    }
+
+    /// Metadata of a cached endpoint, if its body and sidecar are both present
+    pub fn cached(&self, endpoint: &Endpoint) -> Option<CacheMeta> {
+        if !self.cache_path(endpoint).exists() {
+            return None;
+        }
+        let content = std::fs::read_to_string(self.meta_path(endpoint)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Bring one endpoint's cache up to date
+    ///
+    /// Network failures fall back to an existing cache (with a warning);
+    /// they are errors only when nothing is cached. Invalid bodies are
+    /// always errors and leave the cache untouched.
+    pub fn update(&self, endpoint: &Endpoint) -> Result<FetchOutcome, ClientError> {
+        let cached = self.cached(endpoint);
+        let fallback = |warning: Option<String>, error: ClientError| match &cached {
+            Some(meta) => Ok(FetchOutcome {
+                endpoint: endpoint.name.clone(),
+                status: FetchStatus::Cached,
+                path: self.cache_path(endpoint),
+                meta: meta.clone(),
+                warning,
+            }),
+            None => Err(error),
+        };
+
+        if self.offline {
+            return fallback(None, ClientError::NotCached(endpoint.name.clone()));
+        }
+
+        let url = format!("{}/{}", self.base_url, endpoint.path);
+        let agent = ureq::AgentBuilder::new().timeout(self.timeout).build();
+        let mut request = agent.get(&url);
+        if let Some(meta) = &cached {
+            if let Some(etag) = &meta.etag {
+                request = request.set("If-None-Match", etag);
+            }
+            if let Some(last_modified) = &meta.last_modified {
+                request = request.set("If-Modified-Since", last_modified);
+            }
+        }
+
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(e) => {
+                let error = ClientError::Http(format!("{}: {}", url, e));
+                return fallback(Some(error.to_string()), error);
+            }
+        };
+
+        let now = unix_now();
+        if response.status() == 304 {
+            if let Some(mut meta) = cached {
+                meta.checked_at = now;
+                self.write_meta(endpoint, &meta)?;
+                return Ok(FetchOutcome {
+                    endpoint: endpoint.name.clone(),
+                    status: FetchStatus::NotModified,
+                    path: self.cache_path(endpoint),
+                    meta,
+                    warning: None,
+                });
+            }
+        }
+
+        let meta = CacheMeta {
+            url: url.clone(),
+            etag: response.header("ETag").map(str::to_string),
+            last_modified: response.header("Last-Modified").map(str::to_string),
+            fetched_at: now,
+            checked_at: now,
+        };
+        let mut body = String::new();
+        response
+            .into_reader()
+            .read_to_string(&mut body)
+            .map_err(|e| ClientError::Http(format!("{}: {}", url, e)))?;
+        (endpoint.validate)(&body).map_err(|e| ClientError::Invalid(endpoint.name.clone(), e))?;
+
+        self.swap_in(endpoint, &body, &meta)?;
+        Ok(FetchOutcome {
+            endpoint: endpoint.name.clone(),
+            status: FetchStatus::Updated,
+            path: self.cache_path(endpoint),
+            meta,
+            warning: None,
+        })
+    }
+
+    /// Replace the cached body and sidecar
+    ///
+    /// Both are written to temporary files first and renamed into place body
+    /// first, so a failed write leaves the old pair intact.
+    fn swap_in(&self, endpoint: &Endpoint, body: &str, meta: &CacheMeta) -> Result<(), ClientError> {
+        std::fs::create_dir_all(&self.cache_dir).map_err(|e| io_error(&self.cache_dir, e))?;
+        let path = self.cache_path(endpoint);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, body).map_err(|e| io_error(&tmp, e))?;
+        let meta_tmp = self.stage_meta(endpoint, meta)?;
+        std::fs::rename(&tmp, &path).map_err(|e| io_error(&path, e))?;
+        let meta_path = self.meta_path(endpoint);
+        std::fs::rename(&meta_tmp, &meta_path).map_err(|e| io_error(&meta_path, e))
+    }
+
+    fn write_meta(&self, endpoint: &Endpoint, meta: &CacheMeta) -> Result<(), ClientError> {
+        let tmp = self.stage_meta(endpoint, meta)?;
+        let path = self.meta_path(endpoint);
+        std::fs::rename(&tmp, &path).map_err(|e| io_error(&path, e))
+    }
+
+    /// Write a sidecar to a temporary file next to its final path
+    fn stage_meta(&self, endpoint: &Endpoint, meta: &CacheMeta) -> Result<PathBuf, ClientError> {
+        let tmp = self.meta_path(endpoint).with_extension("json.tmp");
+        let json = serde_json::to_string_pretty(meta).expect("cache metadata serializes");
+        std::fs::write(&tmp, json).map_err(|e| io_error(&tmp, e))?;
+        Ok(tmp)
+    }
+}
+
+/// Per-user cache directory: `$XDG_CACHE_HOME/ornament/modomics`, else
+/// `~/.cache/ornament/modomics`
+pub fn default_cache_dir() -> PathBuf {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(|| PathBuf::from(".cache"));
+    base.join("ornament").join("modomics")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default()
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> ClientError {
+    ClientError::IoError(format!("{}: {}", path.display(), e))
+}
+
+/// Errors from updating the MODOMICS cache
+#[derive(Debug)]
+pub enum ClientError {
+    IoError(String),
+    /// The server could not be reached or returned an error status
+    Http(String),
+    /// A response failed validation (endpoint, reason)
+    Invalid(String, String),
+    /// Offline with nothing cached for the endpoint
+    NotCached(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::IoError(e) => write!(f, "IO error: {}", e),
+            ClientError::Http(e) => write!(f, "HTTP error: {}", e),
+            ClientError::Invalid(endpoint, e) => write!(f, "Invalid {} response: {}", endpoint, e),
+            ClientError::NotCached(endpoint) => write!(f, "Offline and no cached copy of {}", endpoint),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    const BODY: &str = r#"{"1": {"id": 1, "name": "1-methyladenosine", "short_name": "m1A", "reference_moiety": ["A"]}}"#;
+
+    /// Local stand-in for MODOMICS serving `body` with ETag "v1"
+    fn serve(body: Arc<Mutex<String>>) -> String {
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let fresh = request.headers().iter()
+                    .any(|h| h.field.equiv("If-None-Match") && h.value.as_str() == "\"v1\"");
+                let etag = tiny_http::Header::from_bytes("ETag", "\"v1\"").unwrap();
+                let response = if fresh {
+                    tiny_http::Response::from_string("").with_status_code(304)
+                } else {
+                    tiny_http::Response::from_string(body.lock().unwrap().clone())
+                };
+                let _ = request.respond(response.with_header(etag));
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_update_uses_etag_and_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let body = Arc::new(Mutex::new(BODY.to_string()));
+        let client = ModomicsClient::new(dir.path()).with_base_url(&serve(body.clone()));
+        let endpoint = Endpoint::modifications();
+
+        let first = client.update(&endpoint).unwrap();
+        assert_eq!(first.status, FetchStatus::Updated);
+        assert_eq!(first.meta.etag.as_deref(), Some("\"v1\""));
+        assert_eq!(std::fs::read_to_string(&first.path).unwrap(), BODY);
+
+        assert_eq!(client.update(&endpoint).unwrap().status, FetchStatus::NotModified);
+
+        let offline = client.clone().with_offline(true).update(&endpoint).unwrap();
+        assert_eq!(offline.status, FetchStatus::Cached);
+        assert!(matches!(
+            client.clone().with_offline(true).update(&Endpoint::trna_sequences()),
+            Err(ClientError::NotCached(_))
+        ));
+
+        // Unreachable server: fall back to the cache with a warning
+        let down = ModomicsClient::new(dir.path()).with_base_url("http://127.0.0.1:1");
+        let fallback = down.update(&endpoint).unwrap();
+        assert_eq!(fallback.status, FetchStatus::Cached);
+        assert!(fallback.warning.is_some());
+    }
+
+    #[test]
+    fn test_invalid_body_keeps_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let body = Arc::new(Mutex::new("{\"1\": {\"id\": \"not a number\"}}".to_string()));
+        let client = ModomicsClient::new(dir.path()).with_base_url(&serve(body.clone()));
+        let endpoint = Endpoint::modifications();
+
+        assert!(matches!(client.update(&endpoint), Err(ClientError::Invalid(..))));
+        assert!(!client.cache_path(&endpoint).exists());
+
+        // A valid cached copy under an older ETag, so the server sends its body
+        let meta = CacheMeta {
+            url: "http://127.0.0.1/api/modifications".to_string(),
+            etag: Some("\"v0\"".to_string()),
+            last_modified: None,
+            fetched_at: 1,
+            checked_at: 1,
+        };
+        client.swap_in(&endpoint, BODY, &meta).unwrap();
+        let cached_body = std::fs::read(client.cache_path(&endpoint)).unwrap();
+        let cached_meta = std::fs::read(client.meta_path(&endpoint)).unwrap();
+
+        assert!(matches!(client.update(&endpoint), Err(ClientError::Invalid(..))));
+        assert_eq!(std::fs::read(client.cache_path(&endpoint)).unwrap(), cached_body);
+        assert_eq!(std::fs::read(client.meta_path(&endpoint)).unwrap(), cached_meta);
+        assert_eq!(client.cached(&endpoint), Some(meta));
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path()).unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+}
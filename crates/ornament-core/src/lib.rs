@@ -14,7 +14,7 @@ pub mod output;
 
 // Re-export commonly used types
 pub use modification::types::{
-    RnaBase, ModCode, Modification, ConservationLevel, FunctionalRole,
+    RnaBase, IupacBase, ModCode, Modification, ChemicalProperties, ConservationLevel, FunctionalRole,
     SprinzlPosition, PositionModExpectation,
 };
 pub use analysis::TRNAHit;
@@ -0,0 +1,165 @@
+//! Sequence-context predicates for modification expectations
+//!
+//! Many modifying enzymes recognize the bases around a site rather than the
+//! isotype alone: t6A37 needs U36, i6A37 an A36-A37-A38 motif, Q34 a GUN
+//! anticodon. A predicate states one such requirement in Sprinzl coordinates
+//! and is evaluated against the bases a tRNA actually carries.
+
+use super::sprinzl::sprinzl_partner;
+use super::types::{IupacBase, RnaBase, SprinzlPosition};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Anticodon positions, 5' to 3'
+const ANTICODON: [&str; 3] = ["34", "35", "36"];
+
+/// A requirement on the sequence context of a modification site
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ContextPredicate {
+    /// The base at a Sprinzl position matches an IUPAC code (e.g. "Y" at 32)
+    Base { position: SprinzlPosition, bases: String },
+    /// The anticodon (34-36, 5' to 3') matches an IUPAC pattern (e.g. "GUN")
+    Anticodon { pattern: String },
+    /// A stem position and its canonical partner form a pair of this kind
+    Pair { position: SprinzlPosition, state: PairState },
+}
+
+/// How two stem bases pair
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PairState {
+    /// Watson-Crick or G-U wobble
+    Paired,
+    /// A-U or G-C only
+    WatsonCrick,
+    /// Neither Watson-Crick nor wobble
+    Mismatch,
+}
+
+impl ContextPredicate {
+    /// Evaluate against the bases at each Sprinzl position of a tRNA
+    ///
+    /// Returns None when a position the predicate needs is missing or ambiguous.
+    pub fn evaluate(&self, bases: &HashMap<SprinzlPosition, RnaBase>) -> Option<bool> {
+        match self {
+            ContextPredicate::Base { position, bases: codes } => {
+                let base = bases.get(position)?;
+                Some(codes.chars().any(|c| iupac_matches(c, *base)))
+            }
+            ContextPredicate::Anticodon { pattern } => {
+                let mut matched = true;
+                for (code, pos) in pattern.chars().zip(ANTICODON) {
+                    let base = bases.get(&SprinzlPosition::new(pos))?;
+                    matched &= iupac_matches(code, *base);
+                }
+                Some(matched)
+            }
+            ContextPredicate::Pair { position, state } => {
+                let a = *bases.get(position)?;
+                let b = *bases.get(&sprinzl_partner(position)?)?;
+                let watson_crick = a.complement() == b;
+                let wobble = matches!((a, b), (RnaBase::G, RnaBase::U) | (RnaBase::U, RnaBase::G));
+                Some(match state {
+                    PairState::Paired => watson_crick || wobble,
+                    PairState::WatsonCrick => watson_crick,
+                    PairState::Mismatch => !watson_crick && !wobble,
+                })
+            }
+        }
+    }
+
+    /// Describe why the predicate is malformed, if it is
+    pub fn problem(&self) -> Option<String> {
+        let bad_code = |codes: &str| codes.chars().find(|&c| !is_iupac(c));
+        match self {
+            ContextPredicate::Base { bases, .. } if bases.is_empty() => {
+                Some("base predicate lists no bases".to_string())
+            }
+            ContextPredicate::Base { bases, .. } => {
+                bad_code(bases).map(|c| format!("'{}' is not an IUPAC nucleotide code", c))
+            }
+            ContextPredicate::Anticodon { pattern } if pattern.chars().count() != 3 => {
+                Some(format!("anticodon pattern '{}' is not three bases long", pattern))
+            }
+            ContextPredicate::Anticodon { pattern } => {
+                bad_code(pattern).map(|c| format!("'{}' is not an IUPAC nucleotide code", c))
+            }
+            ContextPredicate::Pair { position, .. } => match sprinzl_partner(position) {
+                Some(_) => None,
+                None => Some(format!("position {} is not a stem position", position)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ContextPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContextPredicate::Base { position, bases } => write!(f, "{}{}", bases, position),
+            ContextPredicate::Anticodon { pattern } => write!(f, "anticodon {}", pattern),
+            ContextPredicate::Pair { position, state } => {
+                let partner = sprinzl_partner(position)
+                    .map(|p| p.0)
+                    .unwrap_or_else(|| "?".to_string());
+                let state = match state {
+                    PairState::Paired => "paired",
+                    PairState::WatsonCrick => "Watson-Crick",
+                    PairState::Mismatch => "mismatched",
+                };
+                write!(f, "{}-{} {}", position, partner, state)
+            }
+        }
+    }
+}
+
+fn is_iupac(code: char) -> bool {
+    IupacBase::from_char(code).is_some()
+}
+
+/// Whether an IUPAC nucleotide code includes a base
+fn iupac_matches(code: char, base: RnaBase) -> bool {
+    IupacBase::from_char(code).is_some_and(|code| code.contains(base))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bases(pairs: &[(&str, char)]) -> HashMap<SprinzlPosition, RnaBase> {
+        pairs
+            .iter()
+            .map(|(pos, c)| (SprinzlPosition::new(*pos), RnaBase::from_dna_char(*c).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_base_and_anticodon_predicates() {
+        let tyr = bases(&[("32", 'C'), ("34", 'G'), ("35", 'U'), ("36", 'A')]);
+
+        let pyrimidine_32 = ContextPredicate::Base { position: "32".into(), bases: "Y".into() };
+        assert_eq!(pyrimidine_32.evaluate(&tyr), Some(true));
+        assert_eq!(pyrimidine_32.to_string(), "Y32");
+
+        let gun = ContextPredicate::Anticodon { pattern: "GUN".into() };
+        assert_eq!(gun.evaluate(&tyr), Some(true));
+        let nnu = ContextPredicate::Anticodon { pattern: "NNU".into() };
+        assert_eq!(nnu.evaluate(&tyr), Some(false));
+
+        // Unmapped positions leave the predicate undecided
+        let a38 = ContextPredicate::Base { position: "38".into(), bases: "A".into() };
+        assert_eq!(a38.evaluate(&tyr), None);
+    }
+
+    #[test]
+    fn test_pair_predicate() {
+        let paired = ContextPredicate::Pair { position: "10".into(), state: PairState::WatsonCrick };
+        assert_eq!(paired.evaluate(&bases(&[("10", 'G'), ("25", 'C')])), Some(true));
+        assert_eq!(paired.evaluate(&bases(&[("10", 'G'), ("25", 'U')])), Some(false));
+        assert_eq!(paired.to_string(), "10-25 Watson-Crick");
+
+        let loop_pair = ContextPredicate::Pair { position: "17".into(), state: PairState::Paired };
+        assert!(loop_pair.problem().is_some());
+    }
+}
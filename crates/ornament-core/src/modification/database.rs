@@ -2,6 +2,16 @@
 
 use crate::modification::types::*;
 use crate::modification::modomics;
+use crate::modification::taxonomy::Domain;
+use crate::modification::expectations::{
+    ExpectationError, ExpectationFile, ExpectationRecord, EXPECTATION_SCHEMA_VERSION,
+};
+use crate::modification::sprinzl::SprinzlMapper;
+use crate::modification::enzyme::{builtin_enzymes, Enzyme};
+use crate::modification::signature::{builtin_signatures, SequencingSignature};
+use crate::modification::merge::{self, MergeReport, Provenance, Source};
+use crate::modification::resolver::{CodeIndex, CodeKind, ResolveError};
+use crate::modification::snapshot::{DatabaseSnapshot, SnapshotContents, SnapshotError};
 use rustc_hash::FxHashMap;
 use std::path::Path;
 
@@ -11,63 +21,223 @@ pub struct ModificationDatabase {
     modifications: FxHashMap<String, Modification>,
     /// Position-specific expectations (Sprinzl position -> expectations)
     position_expectations: FxHashMap<String, Vec<PositionModExpectation>>,
-    /// Alias mapping (e.g., "Psi" -> "Y")
+    /// Alias mapping (e.g., "Y" -> "Psi")
     aliases: FxHashMap<String, String>,
+    /// Reverse index from every code form to short names
+    codes: CodeIndex,
+    /// Writer enzymes indexed by ID
+    enzymes: FxHashMap<String, Enzyme>,
+    /// Sequencing signatures indexed by modification short name
+    signatures: FxHashMap<String, SequencingSignature>,
+    /// Source of each field of each modification, by short name
+    provenance: FxHashMap<String, Provenance>,
+    /// Outcome of merging a MODOMICS snapshot, if one was used
+    merge_report: Option<MergeReport>,
+    /// Domain whose expectations were loaded
+    domain: Domain,
 }
 
+/// Names of the same modification; the first one in the catalogue is canonical
+const SYNONYMS: &[&[&str]] = &[
+    // Pseudouridine: we use "Psi", MODOMICS uses "Y"
+    &["Psi", "Y", "psi", "Ψ"],
+    // Ribothymidine
+    &["m5U", "rT", "T"],
+];
+
 impl ModificationDatabase {
     /// Create a new database with default eukaryotic modifications
     pub fn eukaryotic() -> Self {
-        let mut db = Self {
-            modifications: FxHashMap::default(),
-            position_expectations: FxHashMap::default(),
-            aliases: FxHashMap::default(),
-        };
+        Self::for_domain(Domain::Eukaryotic)
+    }
+
+    /// Create a database with vertebrate mitochondrial modifications
+    ///
+    /// Mitochondrial tRNAs carry taurine-modified wobble uridines (τm5U34,
+    /// τm5s2U34), methylate purine 9 (m1A9/m1G9) and lack the cytosolic
+    /// m7G46 and D-loop expectations.
+    pub fn mitochondrial() -> Self {
+        Self::for_domain(Domain::Mitochondrial)
+    }
+
+    /// Create a database with bacterial (E. coli-based) expectations
+    ///
+    /// Bacteria carry s4U8, cmo5U34/mnm5s2U34 on wobble uridines, ms2i6A37 and
+    /// edit A34 to inosine only in tRNA-Arg(ACG).
+    pub fn bacterial() -> Self {
+        Self::for_domain(Domain::Bacterial)
+    }
+
+    /// Create a database with archaeal expectations
+    ///
+    /// Archaea carry archaeosine (G+15), m2,2G26, m1Ψ54 in place of m5U54,
+    /// Cm56 and m1I57.
+    pub fn archaeal() -> Self {
+        Self::for_domain(Domain::Archaeal)
+    }
+
+    /// Create a database with plastid (chloroplast) expectations
+    ///
+    /// Plastid tRNAs follow the bacterial pattern they descend from.
+    pub fn plastid() -> Self {
+        Self::for_domain(Domain::Plastid)
+    }
+
+    /// Create a database with the built-in modifications and expectations of a domain
+    pub fn for_domain(domain: Domain) -> Self {
+        let mut db = Self::empty();
+        // The catalogue is shared so expectation files for any domain validate
         db.load_default_modifications();
+        db.load_domain_modifications();
+        db.provenance = db.modifications.keys()
+            .map(|name| (name.clone(), Provenance::uniform(Source::Curated)))
+            .collect();
         db.setup_aliases();
-        db.load_eukaryotic_expectations();
+        db.codes = CodeIndex::build(&db.modifications, &db.aliases);
+        db.load_enzymes();
+        db.load_expectations(domain);
         db
     }
 
     /// Create a database from a MODOMICS JSON file, with eukaryotic position expectations
     pub fn from_modomics_file(path: &Path) -> Result<Self, modomics::ModomicsError> {
-        let modifications = modomics::parse_modomics_file(path)?;
-
-        let mut db = Self {
-            modifications,
-            position_expectations: FxHashMap::default(),
-            aliases: FxHashMap::default(),
-        };
+        Self::from_modomics_file_for_domain(path, Domain::Eukaryotic)
+    }
 
-        db.setup_aliases();
-        db.load_eukaryotic_expectations();
-        Ok(db)
+    /// Create a database from a MODOMICS JSON file, with the position expectations of a domain
+    ///
+    /// The curated built-in modifications are layered over the snapshot (see
+    /// [`merge`](crate::modification::merge)).
+    pub fn from_modomics_file_for_domain(
+        path: &Path,
+        domain: Domain,
+    ) -> Result<Self, modomics::ModomicsError> {
+        let import = modomics::import_modomics_file(path)?;
+        Ok(Self::from_import(import, domain))
     }
 
     /// Create a database from MODOMICS JSON string, with eukaryotic position expectations
     pub fn from_modomics_json(json: &str) -> Result<Self, modomics::ModomicsError> {
-        let modifications = modomics::parse_modomics_json(json)?;
+        Self::from_modomics_json_for_domain(json, Domain::Eukaryotic)
+    }
 
-        let mut db = Self {
-            modifications,
+    /// Create a database from MODOMICS JSON string, with the position expectations of a domain
+    pub fn from_modomics_json_for_domain(
+        json: &str,
+        domain: Domain,
+    ) -> Result<Self, modomics::ModomicsError> {
+        let import = modomics::import_modomics_json(json)?;
+        Ok(Self::from_import(import, domain))
+    }
+
+    fn empty() -> Self {
+        Self {
+            modifications: FxHashMap::default(),
             position_expectations: FxHashMap::default(),
             aliases: FxHashMap::default(),
-        };
+            codes: CodeIndex::default(),
+            enzymes: FxHashMap::default(),
+            signatures: FxHashMap::default(),
+            provenance: FxHashMap::default(),
+            merge_report: None,
+            domain: Domain::default(),
+        }
+    }
+
+    fn from_import(import: modomics::ModomicsImport, domain: Domain) -> Self {
+        let mut db = Self::empty();
+        db.load_default_modifications();
+        db.load_domain_modifications();
+
+        let curated = std::mem::take(&mut db.modifications).into_values().collect();
+        let layered = merge::merge(curated, import, SYNONYMS);
+        db.modifications = layered.modifications;
+        db.provenance = layered.provenance;
+        db.merge_report = Some(layered.report);
 
         db.setup_aliases();
-        db.load_eukaryotic_expectations();
+        db.codes = CodeIndex::build(&db.modifications, &db.aliases);
+        db.load_enzymes();
+        db.load_expectations(domain);
+        db
+    }
+
+    /// Load a database snapshot written by [`to_snapshot`](Self::to_snapshot)
+    pub fn from_snapshot_file(path: &Path) -> Result<Self, SnapshotError> {
+        Self::from_snapshot(DatabaseSnapshot::from_path(path)?)
+    }
+
+    /// Rebuild a database from a snapshot
+    ///
+    /// The expectations are validated against the snapshot's own modifications
+    /// and enzymes.
+    pub fn from_snapshot(snapshot: DatabaseSnapshot) -> Result<Self, SnapshotError> {
+        let contents = snapshot.contents;
+        let mut db = Self::empty();
+        db.domain = contents.domain;
+        db.modifications = contents.modifications.into_iter()
+            .map(|m| (m.short_name.clone(), m))
+            .collect();
+        db.aliases = contents.aliases.into_iter().collect();
+        db.provenance = contents.provenance.into_iter().collect();
+        db.merge_report = contents.merge_report;
+        db.codes = CodeIndex::build(&db.modifications, &db.aliases);
+        db.enzymes = contents.enzymes.into_iter().map(|e| (e.id.clone(), e)).collect();
+        db.signatures = contents.signatures.into_iter().map(|s| (s.modification.clone(), s)).collect();
+
+        let file = ExpectationFile {
+            schema_version: EXPECTATION_SCHEMA_VERSION,
+            name: None,
+            description: None,
+            expectations: contents.expectations,
+        };
+        let expectations = file.resolve(&db, true).map_err(|e| match e {
+            ExpectationError::Invalid(problems) => SnapshotError::Invalid(problems),
+            e => SnapshotError::ParseError(e.to_string()),
+        })?;
+        for expectation in expectations {
+            db.add_position_expectation(expectation);
+        }
         Ok(db)
     }
 
-    /// Set up common aliases (e.g., "Psi" -> "Y")
-    fn setup_aliases(&mut self) {
-        // Pseudouridine: we use "Psi", MODOMICS uses "Y"
-        self.aliases.insert("Psi".to_string(), "Y".to_string());
-        self.aliases.insert("psi".to_string(), "Y".to_string());
+    /// Snapshot of the whole database, with its content hash
+    pub fn to_snapshot(&self) -> DatabaseSnapshot {
+        let mut modifications: Vec<Modification> = self.modifications.values().cloned().collect();
+        modifications.sort_by(|a, b| a.short_name.cmp(&b.short_name));
+
+        DatabaseSnapshot::new(SnapshotContents {
+            domain: self.domain,
+            modifications,
+            aliases: self.aliases.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            enzymes: self.enzymes().into_iter().cloned().collect(),
+            signatures: self.signatures().into_iter().cloned().collect(),
+            expectations: self.export_expectations(None).expectations,
+            provenance: self.provenance.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            merge_report: self.merge_report.clone(),
+        })
+    }
+
+    /// Content hash identifying this database (see [`snapshot`](crate::modification::snapshot))
+    pub fn content_hash(&self) -> String {
+        self.to_snapshot().content_hash
+    }
+
+    /// Domain whose built-in expectations the database was created with
+    pub fn domain(&self) -> Domain {
+        self.domain
+    }
 
-        // Ribothymidine aliases
-        self.aliases.insert("rT".to_string(), "m5U".to_string());
-        self.aliases.insert("T".to_string(), "m5U".to_string());
+    /// Point every synonym (e.g. "Y", "rT") at the name the catalogue uses
+    fn setup_aliases(&mut self) {
+        for group in SYNONYMS {
+            let Some(canonical) = group.iter().find(|name| self.modifications.contains_key(**name)) else {
+                continue;
+            };
+            for name in group.iter().filter(|name| *name != canonical) {
+                self.aliases.insert(name.to_string(), canonical.to_string());
+            }
+        }
     }
 
     /// Get a modification by short name (checks aliases)
@@ -89,6 +259,116 @@ impl ModificationDatabase {
         &self.modifications
     }
 
+    /// Resolve any form of modification code: short name, synonym, ChEBI ID,
+    /// modkit code, single-character code or MODOMICS abbreviation
+    pub fn resolve(&self, code: &str) -> Result<&Modification, ResolveError> {
+        let name = self.codes.lookup(code, None)?;
+        Ok(&self.modifications[name])
+    }
+
+    /// Resolve a code of one particular form
+    pub fn resolve_as(&self, code: &str, kind: CodeKind) -> Result<&Modification, ResolveError> {
+        let name = self.codes.lookup(code, Some(kind))?;
+        Ok(&self.modifications[name])
+    }
+
+    /// Source of each field of a modification (checks aliases)
+    pub fn provenance(&self, short_name: &str) -> Option<&Provenance> {
+        let modification = self.get_modification(short_name)?;
+        self.provenance.get(&modification.short_name)
+    }
+
+    /// What merging the MODOMICS snapshot did (None for the built-in catalogue)
+    pub fn merge_report(&self) -> Option<&MergeReport> {
+        self.merge_report.as_ref()
+    }
+
+    /// Get a modification by its MODOMICS ID
+    pub fn get_by_modomics_id(&self, id: u32) -> Option<&Modification> {
+        self.modifications.values().find(|m| m.modomics_id == Some(id))
+    }
+
+    /// Modifications whose monoisotopic mass is within `tolerance` Da of `mass`,
+    /// closest first
+    pub fn find_by_mass(&self, mass: f64, tolerance: f64) -> Vec<&Modification> {
+        let deviation = |m: &Modification| {
+            let monoiso = m.chemistry.as_ref()?.mass_monoiso?;
+            Some((monoiso - mass).abs()).filter(|d| *d <= tolerance)
+        };
+        let mut hits: Vec<(f64, &Modification)> = self.modifications.values()
+            .filter_map(|m| deviation(m).map(|d| (d, m)))
+            .collect();
+        hits.sort_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.short_name.cmp(&b.1.short_name)));
+        hits.into_iter().map(|(_, m)| m).collect()
+    }
+
+    /// Modifications with a given molecular formula
+    pub fn find_by_formula(&self, formula: &str) -> Vec<&Modification> {
+        let mut hits: Vec<&Modification> = self.modifications.values()
+            .filter(|m| m.chemistry.as_ref().and_then(|c| c.formula.as_deref()) == Some(formula))
+            .collect();
+        hits.sort_by(|a, b| a.short_name.cmp(&b.short_name));
+        hits
+    }
+
+    /// Get a writer enzyme by ID
+    pub fn get_enzyme(&self, id: &str) -> Option<&Enzyme> {
+        self.enzymes.get(id)
+    }
+
+    /// Find an enzyme by ID or by any of its gene names (case-insensitive)
+    pub fn find_enzyme(&self, name: &str) -> Option<&Enzyme> {
+        if let Some(enzyme) = self.enzymes.get(name) {
+            return Some(enzyme);
+        }
+        self.enzymes().into_iter().find(|e| {
+            e.id.eq_ignore_ascii_case(name)
+                || e.genes.values().flatten().any(|gene| gene.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// All writer enzymes, sorted by ID
+    pub fn enzymes(&self) -> Vec<&Enzyme> {
+        let mut enzymes: Vec<&Enzyme> = self.enzymes.values().collect();
+        enzymes.sort_by(|a, b| a.id.cmp(&b.id));
+        enzymes
+    }
+
+    /// Sequencing signature of a modification, by any of its names
+    pub fn signature(&self, name: &str) -> Option<&SequencingSignature> {
+        self.signatures.get(name).or_else(|| {
+            let short_name = &self.get_modification(name)?.short_name;
+            self.signatures.get(short_name)
+        })
+    }
+
+    /// All sequencing signatures, sorted by modification
+    pub fn signatures(&self) -> Vec<&SequencingSignature> {
+        let mut signatures: Vec<&SequencingSignature> = self.signatures.values().collect();
+        signatures.sort_by(|a, b| a.modification.cmp(&b.modification));
+        signatures
+    }
+
+    /// Whether two names refer to the same modification (e.g. "Psi" and "Y")
+    pub fn same_modification(&self, a: &str, b: &str) -> bool {
+        let resolve = |name: &str| {
+            self.get_modification(name)
+                .map(|m| m.short_name.clone())
+                .unwrap_or_else(|| name.to_string())
+        };
+        resolve(a) == resolve(b)
+    }
+
+    /// Enzymes of an expectation that install a particular modification
+    pub fn enzymes_for(&self, expectation: &PositionModExpectation, modification: &Modification) -> Vec<&Enzyme> {
+        expectation
+            .enzymes
+            .iter()
+            .filter_map(|id| self.get_enzyme(id))
+            .filter(|e| e.modifications.iter().any(|m| self.same_modification(m, &modification.short_name)))
+            .collect()
+    }
+
     /// Get all expected modifications at a Sprinzl position
     pub fn get_expectations(&self, position: &SprinzlPosition) -> Vec<&PositionModExpectation> {
         self.position_expectations
@@ -109,6 +389,47 @@ impl ModificationDatabase {
             .collect()
     }
 
+    /// All position expectations in Sprinzl order
+    pub fn expectations(&self) -> Vec<&PositionModExpectation> {
+        let mapper = SprinzlMapper::new_standard();
+        let mut positions: Vec<&String> = self.position_expectations.keys().collect();
+        positions.sort_by_key(|p| mapper.sort_key(&SprinzlPosition::new(p.as_str())));
+
+        positions
+            .into_iter()
+            .flat_map(|p| self.position_expectations[p].iter())
+            .collect()
+    }
+
+    /// Replace all position expectations with those from an expectation file
+    ///
+    /// The file is validated against this database's modifications first; on
+    /// error the current expectations are left untouched.
+    pub fn replace_expectations(&mut self, file: &ExpectationFile) -> Result<(), ExpectationError> {
+        let expectations = file.resolve(self, true)?;
+        self.position_expectations.clear();
+        for expectation in expectations {
+            self.add_position_expectation(expectation);
+        }
+        Ok(())
+    }
+
+    /// Replace all position expectations with those from a TOML/JSON file
+    pub fn load_expectation_file(&mut self, path: &Path) -> Result<(), ExpectationError> {
+        let file = ExpectationFile::from_path(path)?;
+        self.replace_expectations(&file)
+    }
+
+    /// Export the current position expectations as an expectation file
+    pub fn export_expectations(&self, name: Option<&str>) -> ExpectationFile {
+        ExpectationFile {
+            schema_version: EXPECTATION_SCHEMA_VERSION,
+            name: name.map(str::to_string),
+            description: None,
+            expectations: self.expectations().into_iter().map(ExpectationRecord::from).collect(),
+        }
+    }
+
     fn load_default_modifications(&mut self) {
         // Pseudouridine (Psi/Y) - most common modification
         self.add_modification(Modification {
@@ -121,6 +442,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
             chebi_id: Some(17802),
             modomics_unicode: Some('Ψ'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // Dihydrouridine (D)
@@ -134,6 +457,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
             chebi_id: Some(15802),
             modomics_unicode: Some('D'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // 5-methyluridine (m5U/T/rT) - ribothymidine
@@ -147,6 +472,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
             chebi_id: Some(16695),
             modomics_unicode: Some('T'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // 1-methyladenosine (m1A)
@@ -160,6 +487,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
             chebi_id: Some(21837),
             modomics_unicode: Some('"'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // 1-methylguanosine (m1G)
@@ -173,6 +502,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::C, RnaBase::U],
             chebi_id: Some(21836),
             modomics_unicode: Some('K'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // N6-threonylcarbamoyladenosine (t6A)
@@ -186,6 +517,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
             chebi_id: Some(20817),
             modomics_unicode: Some('6'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // N6-isopentenyladenosine (i6A)
@@ -199,6 +532,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
             chebi_id: Some(17588),
             modomics_unicode: Some('+'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // Inosine (I) - A to I editing at wobble position
@@ -212,6 +547,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
             chebi_id: Some(17596),
             modomics_unicode: Some('I'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // Queuosine (Q)
@@ -225,6 +562,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::C, RnaBase::U],
             chebi_id: Some(17399),
             modomics_unicode: Some('Q'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // 2'-O-methylcytidine (Cm)
@@ -238,6 +577,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::U],
             chebi_id: Some(19228),
             modomics_unicode: Some('B'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // 5-methylcytidine (m5C)
@@ -251,6 +592,8 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::U],
             chebi_id: Some(27480),
             modomics_unicode: Some('?'),
+            modomics_id: None,
+            chemistry: None,
         });
 
         // 7-methylguanosine (m7G)
@@ -264,189 +607,240 @@ impl ModificationDatabase {
             incompatible_bases: vec![RnaBase::A, RnaBase::C, RnaBase::U],
             chebi_id: Some(2274),
             modomics_unicode: Some('7'),
+            modomics_id: None,
+            chemistry: None,
         });
     }
 
-    /// Helper to get a cloned modification by name, checking aliases
-    fn get_mod_cloned(&self, name: &str) -> Option<Modification> {
-        // First try direct lookup
-        if let Some(m) = self.modifications.get(name) {
-            return Some(m.clone());
-        }
-        // Then try alias lookup
-        if let Some(alias) = self.aliases.get(name) {
-            return self.modifications.get(alias).cloned();
-        }
-        None
-    }
+    /// Modifications needed by the non-eukaryotic expectation sets
+    fn load_domain_modifications(&mut self) {
+        // 5-taurinomethyluridine (tm5U) - mito wobble uridine
+        self.add_modification(Modification {
+            name: "5-taurinomethyluridine".to_string(),
+            short_name: "tm5U".to_string(),
+            code: ModCode::ShortName("tm5U".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::U,
+            genomic_expectation: RnaBase::U,
+            incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
+            chebi_id: None,
+            modomics_unicode: Some('ʭ'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-    fn load_eukaryotic_expectations(&mut self) {
-        // Position 8 - Usually A, sometimes modified
-        // (Not adding modification requirement)
-
-        // Position 13-17 - D-loop dihydrouridines
-        if let Some(d) = self.get_mod_cloned("D") {
-            for pos in [16, 17, 20] {
-                self.add_position_expectation(PositionModExpectation {
-                    position: SprinzlPosition::from_num(pos),
-                    modifications: vec![d.clone()],
-                    conservation: ConservationLevel::Universal,
-                    functional_role: FunctionalRole::StructuralStability,
-                    isotypes: vec![],
-                });
-            }
-        }
+        // 5-taurinomethyl-2-thiouridine (tm5s2U)
+        self.add_modification(Modification {
+            name: "5-taurinomethyl-2-thiouridine".to_string(),
+            short_name: "tm5s2U".to_string(),
+            code: ModCode::ShortName("tm5s2U".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::U,
+            genomic_expectation: RnaBase::U,
+            incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
+            chebi_id: None,
+            modomics_unicode: Some('ƕ'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Position 32 - Cm in some tRNAs
-        if let Some(cm) = self.get_mod_cloned("Cm") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(32),
-                modifications: vec![cm],
-                conservation: ConservationLevel::IsotypeSpecific,
-                functional_role: FunctionalRole::AnticodonFunction,
-                isotypes: vec![Isotype::PHE.to_string(), Isotype::TRP.to_string()],
-            });
-        }
+        // 2-methylthio-N6-isopentenyladenosine (ms2i6A)
+        self.add_modification(Modification {
+            name: "2-methylthio-N6-isopentenyladenosine".to_string(),
+            short_name: "ms2i6A".to_string(),
+            code: ModCode::ShortName("ms2i6A".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::A,
+            genomic_expectation: RnaBase::A,
+            incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: Some('*'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Position 34 - Wobble position - various modifications
-        // Inosine in specific tRNAs
-        if let Some(i) = self.get_mod_cloned("I") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(34),
-                modifications: vec![i],
-                conservation: ConservationLevel::IsotypeSpecific,
-                functional_role: FunctionalRole::AnticodonFunction,
-                isotypes: vec![
-                    Isotype::ALA.to_string(),
-                    Isotype::ARG.to_string(),
-                    Isotype::ILE.to_string(),
-                    Isotype::LEU.to_string(),
-                    Isotype::PRO.to_string(),
-                    Isotype::SER.to_string(),
-                    Isotype::THR.to_string(),
-                    Isotype::VAL.to_string(),
-                ],
-            });
-        }
+        // 4-thiouridine (s4U) - bacterial/plastid position 8
+        self.add_modification(Modification {
+            name: "4-thiouridine".to_string(),
+            short_name: "s4U".to_string(),
+            code: ModCode::ShortName("s4U".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::U,
+            genomic_expectation: RnaBase::U,
+            incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
+            chebi_id: None,
+            modomics_unicode: Some('4'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Queuosine at position 34 for specific isotypes
-        if let Some(q) = self.get_mod_cloned("Q") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(34),
-                modifications: vec![q],
-                conservation: ConservationLevel::IsotypeSpecific,
-                functional_role: FunctionalRole::AnticodonFunction,
-                isotypes: vec![
-                    Isotype::ASN.to_string(),
-                    Isotype::ASP.to_string(),
-                    Isotype::HIS.to_string(),
-                    Isotype::TYR.to_string(),
-                ],
-            });
-        }
+        // Uridine 5-oxyacetic acid (cmo5U) - bacterial wobble uridine
+        self.add_modification(Modification {
+            name: "uridine 5-oxyacetic acid".to_string(),
+            short_name: "cmo5U".to_string(),
+            code: ModCode::ShortName("cmo5U".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::U,
+            genomic_expectation: RnaBase::U,
+            incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
+            chebi_id: None,
+            modomics_unicode: Some('V'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Position 37 - 3' of anticodon - hypermodified in most tRNAs
-        // t6A is common
-        if let Some(t6a) = self.get_mod_cloned("t6A") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(37),
-                modifications: vec![t6a],
-                conservation: ConservationLevel::DomainSpecific,
-                functional_role: FunctionalRole::AnticodonFunction,
-                isotypes: vec![
-                    Isotype::ILE.to_string(),
-                    Isotype::LYS.to_string(),
-                    Isotype::ASN.to_string(),
-                    Isotype::SER.to_string(),
-                    Isotype::THR.to_string(),
-                ],
-            });
-        }
+        // 5-methylaminomethyl-2-thiouridine (mnm5s2U) - bacterial wobble uridine
+        self.add_modification(Modification {
+            name: "5-methylaminomethyl-2-thiouridine".to_string(),
+            short_name: "mnm5s2U".to_string(),
+            code: ModCode::ShortName("mnm5s2U".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::U,
+            genomic_expectation: RnaBase::U,
+            incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
+            chebi_id: None,
+            modomics_unicode: Some('S'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // i6A at position 37
-        if let Some(i6a) = self.get_mod_cloned("i6A") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(37),
-                modifications: vec![i6a],
-                conservation: ConservationLevel::IsotypeSpecific,
-                functional_role: FunctionalRole::AnticodonFunction,
-                isotypes: vec![
-                    Isotype::CYS.to_string(),
-                    Isotype::SER.to_string(),
-                    Isotype::TRP.to_string(),
-                ],
-            });
-        }
+        // Archaeosine (G+) - archaeal position 15
+        self.add_modification(Modification {
+            name: "archaeosine".to_string(),
+            short_name: "G+".to_string(),
+            code: ModCode::ShortName("G+".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::G,
+            genomic_expectation: RnaBase::G,
+            incompatible_bases: vec![RnaBase::A, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: Some('('),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // m1G at position 37 for some isotypes
-        if let Some(m1g) = self.get_mod_cloned("m1G") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(37),
-                modifications: vec![m1g],
-                conservation: ConservationLevel::IsotypeSpecific,
-                functional_role: FunctionalRole::AnticodonFunction,
-                isotypes: vec![
-                    Isotype::ALA.to_string(),
-                    Isotype::ARG.to_string(),
-                    Isotype::LEU.to_string(),
-                    Isotype::PRO.to_string(),
-                ],
-            });
-        }
+        // N2,N2-dimethylguanosine (m2,2G) - position 26
+        self.add_modification(Modification {
+            name: "N2,N2-dimethylguanosine".to_string(),
+            short_name: "m2,2G".to_string(),
+            code: ModCode::ShortName("m2,2G".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::G,
+            genomic_expectation: RnaBase::G,
+            incompatible_bases: vec![RnaBase::A, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: Some('R'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Position 46 - m7G
-        if let Some(m7g) = self.get_mod_cloned("m7G") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(46),
-                modifications: vec![m7g],
-                conservation: ConservationLevel::Universal,
-                functional_role: FunctionalRole::StructuralStability,
-                isotypes: vec![],
-            });
-        }
+        // 1-methylpseudouridine (m1Y) - archaeal position 54
+        self.add_modification(Modification {
+            name: "1-methylpseudouridine".to_string(),
+            short_name: "m1Y".to_string(),
+            code: ModCode::ShortName("m1Y".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::U,
+            genomic_expectation: RnaBase::U,
+            incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
+            chebi_id: None,
+            modomics_unicode: Some(']'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Position 48/49 - m5C
-        if let Some(m5c) = self.get_mod_cloned("m5C") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(48),
-                modifications: vec![m5c],
-                conservation: ConservationLevel::DomainSpecific,
-                functional_role: FunctionalRole::StructuralStability,
-                isotypes: vec![],
-            });
-        }
+        // 1-methylinosine (m1I) - archaeal position 57
+        self.add_modification(Modification {
+            name: "1-methylinosine".to_string(),
+            short_name: "m1I".to_string(),
+            code: ModCode::ShortName("m1I".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::A,
+            genomic_expectation: RnaBase::A,
+            incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: Some('O'),
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Position 54 - m5U (ribothymidine) - nearly universal
-        if let Some(m5u) = self.get_mod_cloned("m5U") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(54),
-                modifications: vec![m5u],
-                conservation: ConservationLevel::Universal,
-                functional_role: FunctionalRole::StructuralStability,
-                isotypes: vec![],
-            });
-        }
+        // 2'-O-methylguanosine (Gm) - eukaryotic tRNA-Phe position 34
+        self.add_modification(Modification {
+            name: "2'-O-methylguanosine".to_string(),
+            short_name: "Gm".to_string(),
+            code: ModCode::ShortName("Gm".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::G,
+            genomic_expectation: RnaBase::G,
+            incompatible_bases: vec![RnaBase::A, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: None,
+            modomics_id: None,
+            chemistry: None,
+        });
+
+        // Wybutosine (yW) - eukaryotic tRNA-Phe position 37, built on m1G37
+        self.add_modification(Modification {
+            name: "wybutosine".to_string(),
+            short_name: "yW".to_string(),
+            code: ModCode::ShortName("yW".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::G,
+            genomic_expectation: RnaBase::G,
+            incompatible_bases: vec![RnaBase::A, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: None,
+            modomics_id: None,
+            chemistry: None,
+        });
+
+        // Cyclic N6-threonylcarbamoyladenosine (ct6A) - made from t6A37
+        self.add_modification(Modification {
+            name: "cyclic N6-threonylcarbamoyladenosine".to_string(),
+            short_name: "ct6A".to_string(),
+            code: ModCode::ShortName("ct6A".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::A,
+            genomic_expectation: RnaBase::A,
+            incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: None,
+            modomics_id: None,
+            chemistry: None,
+        });
 
-        // Position 55 - Pseudouridine - universal (try both Psi and Y)
-        if let Some(psi) = self.get_mod_cloned("Psi").or_else(|| self.get_mod_cloned("Y")) {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(55),
-                modifications: vec![psi],
-                conservation: ConservationLevel::Universal,
-                functional_role: FunctionalRole::StructuralStability,
-                isotypes: vec![],
-            });
+        // 2-methylthio-N6-threonylcarbamoyladenosine (ms2t6A) - made from t6A37
+        self.add_modification(Modification {
+            name: "2-methylthio-N6-threonylcarbamoyladenosine".to_string(),
+            short_name: "ms2t6A".to_string(),
+            code: ModCode::ShortName("ms2t6A".to_string()),
+            alt_codes: vec![],
+            parent_base: RnaBase::A,
+            genomic_expectation: RnaBase::A,
+            incompatible_bases: vec![RnaBase::G, RnaBase::C, RnaBase::U],
+            chebi_id: None,
+            modomics_unicode: None,
+            modomics_id: None,
+            chemistry: None,
+        });
+    }
+
+    fn load_enzymes(&mut self) {
+        for enzyme in builtin_enzymes() {
+            self.enzymes.insert(enzyme.id.clone(), enzyme);
+        }
+        for signature in builtin_signatures() {
+            self.signatures.insert(signature.modification.clone(), signature);
         }
+    }
 
-        // Position 58 - m1A - very common
-        if let Some(m1a) = self.get_mod_cloned("m1A") {
-            self.add_position_expectation(PositionModExpectation {
-                position: SprinzlPosition::from_num(58),
-                modifications: vec![m1a],
-                conservation: ConservationLevel::Universal,
-                functional_role: FunctionalRole::StructuralStability,
-                isotypes: vec![],
-            });
+    fn load_expectations(&mut self, domain: Domain) {
+        self.domain = domain;
+        let expectations = ExpectationFile::builtin(domain)
+            .resolve(self, false)
+            .expect("lenient resolution never fails");
+        for expectation in expectations {
+            self.add_position_expectation(expectation);
         }
     }
 
@@ -500,6 +894,87 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_mitochondrial_database() {
+        let db = ModificationDatabase::mitochondrial();
+
+        assert!(db.get_modification("tm5U").is_some());
+
+        // No m7G46 or D-loop expectations in mitochondria
+        assert!(db.get_expectations(&SprinzlPosition::from_num(46)).is_empty());
+        assert!(db.get_expectations(&SprinzlPosition::from_num(16)).is_empty());
+
+        // Position 9 accepts either m1A or m1G
+        let exp9 = db.get_expectations(&SprinzlPosition::from_num(9));
+        assert_eq!(exp9[0].modifications.len(), 2);
+    }
+
+    #[test]
+    fn test_domain_databases() {
+        let bact = ModificationDatabase::bacterial();
+
+        // s4U8 is bacterial only
+        assert!(!bact.get_expectations(&SprinzlPosition::from_num(8)).is_empty());
+        assert!(ModificationDatabase::eukaryotic()
+            .get_expectations(&SprinzlPosition::from_num(8))
+            .is_empty());
+
+        // I34 only applies to Arg in bacteria
+        let exp34_ala = bact.get_expectations_for_isotype(&SprinzlPosition::from_num(34), &Isotype::new("Ala"));
+        assert!(!exp34_ala.iter().any(|e| e.modifications.iter().any(|m| m.short_name == "I")));
+        let exp34_arg = bact.get_expectations_for_isotype(&SprinzlPosition::from_num(34), &Isotype::new("Arg"));
+        assert!(exp34_arg.iter().any(|e| e.modifications.iter().any(|m| m.short_name == "I")));
+
+        let arch = ModificationDatabase::archaeal();
+        let exp15 = arch.get_expectations(&SprinzlPosition::from_num(15));
+        assert_eq!(exp15[0].modifications[0].short_name, "G+");
+
+        let plastid = ModificationDatabase::plastid();
+        assert!(!plastid.get_expectations(&SprinzlPosition::from_num(37)).is_empty());
+    }
+
+    #[test]
+    fn test_expectation_export_round_trip() {
+        let db = ModificationDatabase::archaeal();
+        let exported = db.export_expectations(Some("archaeal"));
+        assert_eq!(exported.expectations.len(), db.expectations().len());
+        assert_eq!(exported.expectations[0].position, SprinzlPosition::from_num(15));
+
+        let toml = exported.to_toml_string().unwrap();
+        let mut euk = ModificationDatabase::eukaryotic();
+        euk.replace_expectations(&ExpectationFile::from_toml_str(&toml).unwrap()).unwrap();
+
+        assert!(euk.get_expectations(&SprinzlPosition::from_num(46)).is_empty());
+        assert!(!euk.get_expectations(&SprinzlPosition::from_num(15)).is_empty());
+    }
+
+    #[test]
+    fn test_chemistry_queries() {
+        let json = r#"{
+            "83": {
+                "id": 83, "name": "1-methyladenosine", "short_name": "m1A",
+                "reference_moiety": ["A"], "formula": "C11H15N5O4", "mass_monoiso": 281.1124
+            },
+            "96": {
+                "id": 96, "name": "N6-methyladenosine", "short_name": "m6A",
+                "reference_moiety": ["A"], "formula": "C11H15N5O4", "mass_monoiso": 281.1124
+            },
+            "118": {
+                "id": 118, "name": "pseudouridine", "short_name": "Y",
+                "reference_moiety": ["U"], "formula": "C9H12N2O6", "mass_monoiso": 244.0695
+            }
+        }"#;
+        let db = ModificationDatabase::from_modomics_json(json).unwrap();
+
+        assert_eq!(db.get_by_modomics_id(118).unwrap().short_name, "Psi");
+        assert!(db.get_by_modomics_id(1).is_none());
+
+        let isobaric: Vec<&str> = db.find_by_mass(281.11, 0.01).iter().map(|m| m.short_name.as_str()).collect();
+        assert_eq!(isobaric, ["m1A", "m6A"]);
+        assert!(db.find_by_mass(250.0, 0.5).is_empty());
+        assert_eq!(db.find_by_formula("C9H12N2O6")[0].short_name, "Psi");
+    }
+
     #[test]
     fn test_from_modomics_json() {
         // Minimal MODOMICS-format JSON with key modifications
@@ -534,8 +1009,21 @@ mod tests {
         assert!(db.get_modification("Y").is_some());
         assert!(db.get_modification("I").is_some());
 
-        // Alias should work: "Psi" -> "Y"
-        assert!(db.get_modification("Psi").is_some());
+        // Curated naming is kept, with the MODOMICS name as an alias
+        assert_eq!(db.get_modification("Y").unwrap().short_name, "Psi");
+
+        // Curated fields survive the merge; MODOMICS supplies its own
+        let psi = db.get_modification("Psi").unwrap();
+        assert_eq!(psi.chebi_id, Some(17802));
+        assert_eq!(psi.modomics_id, Some(118));
+        let provenance = db.provenance("Y").unwrap();
+        assert_eq!(provenance.source("chebi_id"), Some(Source::Curated));
+        assert_eq!(provenance.source("modomics_id"), Some(Source::Modomics));
+
+        let report = db.merge_report().unwrap();
+        assert_eq!(report.merged, ["D", "I", "Psi"]);
+        assert!(report.curated_only.contains(&"m1A".to_string()));
+        assert!(report.conflicts.iter().any(|c| c.modification == "Psi" && c.field == "modomics_unicode"));
 
         // Position expectations should be set for available mods
         let exp16 = db.get_expectations(&SprinzlPosition::from_num(16));
@@ -0,0 +1,223 @@
+//! Differences between MODOMICS releases
+//!
+//! Compares two parsed snapshots modification by modification, then works out
+//! which position expectations of a database name an added, removed or
+//! changed modification and which tRNAs change odd status when a saved
+//! analysis input is re-run against both.
+
+use super::database::ModificationDatabase;
+use super::types::{Modification, SprinzlPosition};
+use crate::analysis::ModCompatibilityResult;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A field that differs between releases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// A modification present in both releases with differing fields
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangedModification {
+    pub short_name: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// How a modification differs between releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeKind::Added => write!(f, "added"),
+            ChangeKind::Removed => write!(f, "removed"),
+            ChangeKind::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+/// Added, removed and changed modifications, by short name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModomicsDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedModification>,
+}
+
+/// A position expectation naming a modification that differs between releases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AffectedExpectation {
+    pub position: SprinzlPosition,
+    /// Isotypes the expectation applies to (empty = all)
+    pub isotypes: Vec<String>,
+    /// Short name as the database uses it
+    pub modification: String,
+    pub change: ChangeKind,
+}
+
+/// A tRNA whose odd status differs between two analyses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OddStatusChange {
+    pub trna_id: String,
+    pub seq_name: String,
+    pub old_score: f64,
+    pub new_score: f64,
+    pub was_odd: bool,
+    pub now_odd: bool,
+}
+
+/// Compare two parsed MODOMICS snapshots
+pub fn diff_modifications(
+    old: &FxHashMap<String, Modification>,
+    new: &FxHashMap<String, Modification>,
+) -> ModomicsDiff {
+    let mut diff = ModomicsDiff::default();
+
+    for (name, new_mod) in new {
+        match old.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(old_mod) => {
+                let changes = field_changes(old_mod, new_mod);
+                if !changes.is_empty() {
+                    diff.changed.push(ChangedModification { short_name: name.clone(), changes });
+                }
+            }
+        }
+    }
+    diff.removed = old.keys().filter(|name| !new.contains_key(*name)).cloned().collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort_by(|a, b| a.short_name.cmp(&b.short_name));
+    diff
+}
+
+/// Fields compared between releases: identity, parent base, abbreviations and masses
+fn field_changes(old: &Modification, new: &Modification) -> Vec<FieldChange> {
+    fn show<T: ToString>(value: Option<T>) -> String {
+        value.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string())
+    }
+    let chemistry = |m: &Modification| m.chemistry.clone().unwrap_or_default();
+    let (old_chem, new_chem) = (chemistry(old), chemistry(new));
+
+    let fields = [
+        ("name", old.name.clone(), new.name.clone()),
+        ("parent_base", old.parent_base.to_string(), new.parent_base.to_string()),
+        ("code", old.code.to_string(), new.code.to_string()),
+        ("modomics_unicode", show(old.modomics_unicode), show(new.modomics_unicode)),
+        ("chebi_id", show(old.chebi_id), show(new.chebi_id)),
+        ("modomics_id", show(old.modomics_id), show(new.modomics_id)),
+        ("formula", show(old_chem.formula), show(new_chem.formula)),
+        ("mass_avg", show(old_chem.mass_avg), show(new_chem.mass_avg)),
+        ("mass_monoiso", show(old_chem.mass_monoiso), show(new_chem.mass_monoiso)),
+    ];
+    fields
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| FieldChange { field: field.to_string(), old, new })
+        .collect()
+}
+
+impl ModomicsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// How a modification differs, matching synonyms through the database
+    fn change_for(&self, db: &ModificationDatabase, short_name: &str) -> Option<ChangeKind> {
+        let matches = |name: &String| db.same_modification(name, short_name);
+        if self.removed.iter().any(matches) {
+            Some(ChangeKind::Removed)
+        } else if self.added.iter().any(matches) {
+            Some(ChangeKind::Added)
+        } else if self.changed.iter().any(|c| matches(&c.short_name)) {
+            Some(ChangeKind::Changed)
+        } else {
+            None
+        }
+    }
+
+    /// Position expectations of a database that name a modification in the diff
+    pub fn affected_expectations(&self, db: &ModificationDatabase) -> Vec<AffectedExpectation> {
+        db.expectations()
+            .into_iter()
+            .flat_map(|exp| {
+                exp.modifications.iter().filter_map(move |m| {
+                    Some(AffectedExpectation {
+                        position: exp.position.clone(),
+                        isotypes: exp.isotypes.clone(),
+                        modification: m.short_name.clone(),
+                        change: self.change_for(db, &m.short_name)?,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+/// tRNAs whose odd status differs between two analyses of the same hits
+pub fn odd_status_changes(old: &[ModCompatibilityResult], new: &[ModCompatibilityResult]) -> Vec<OddStatusChange> {
+    old.iter()
+        .zip(new)
+        .filter(|(o, n)| o.is_odd != n.is_odd)
+        .map(|(o, n)| OddStatusChange {
+            trna_id: o.hit.id.clone(),
+            seq_name: o.hit.seq_name.clone(),
+            old_score: o.compatibility_score,
+            new_score: n.compatibility_score,
+            was_odd: o.is_odd,
+            now_odd: n.is_odd,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::parse_modomics_json;
+
+    #[test]
+    fn test_diff_and_affected_expectations() {
+        let old = parse_modomics_json(r#"{
+            "1": {"id": 1, "name": "1-methyladenosine", "short_name": "m1A", "new_abbrev": "\"", "reference_moiety": ["A"], "mass_monoiso": 281.1124},
+            "2": {"id": 2, "name": "pseudouridine", "short_name": "Y", "new_abbrev": "P", "reference_moiety": ["U"]},
+            "3": {"id": 3, "name": "dihydrouridine", "short_name": "D", "new_abbrev": "D", "reference_moiety": ["U"]}
+        }"#).unwrap();
+        let new = parse_modomics_json(r#"{
+            "1": {"id": 1, "name": "1-methyladenosine", "short_name": "m1A", "new_abbrev": "\"", "reference_moiety": ["A"], "mass_monoiso": 281.1125},
+            "3": {"id": 3, "name": "dihydrouridine", "short_name": "D", "new_abbrev": "D", "reference_moiety": ["U"]},
+            "4": {"id": 4, "name": "7-methylguanosine", "short_name": "m7G", "new_abbrev": "7", "reference_moiety": ["G"]}
+        }"#).unwrap();
+
+        let diff = diff_modifications(&old, &new);
+        assert_eq!(diff.added, ["m7G"]);
+        assert_eq!(diff.removed, ["Y"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].changes, [FieldChange {
+            field: "mass_monoiso".to_string(),
+            old: "281.1124".to_string(),
+            new: "281.1125".to_string(),
+        }]);
+
+        // "Y" is the database's Psi
+        let db = ModificationDatabase::eukaryotic();
+        let affected = diff.affected_expectations(&db);
+        let change_at = |pos: &str, name: &str| affected.iter()
+            .find(|a| a.position.0 == pos && a.modification == name)
+            .map(|a| a.change);
+        assert_eq!(change_at("55", "Psi"), Some(ChangeKind::Removed));
+        assert_eq!(change_at("58", "m1A"), Some(ChangeKind::Changed));
+        assert_eq!(change_at("46", "m7G"), Some(ChangeKind::Added));
+        assert!(affected.iter().all(|a| a.modification != "D"));
+    }
+}
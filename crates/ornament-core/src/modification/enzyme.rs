@@ -0,0 +1,79 @@
+//! Modification writer enzymes
+//!
+//! Links expectations to the enzymes that install them, so a lost site can be
+//! reported as a lost substrate of, say, TrmA or Tgt. The records are a bundled
+//! table (`data/enzymes.toml`) shared by all domains.
+
+use super::context::ContextPredicate;
+use super::taxonomy::Domain;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Current version of the enzyme table schema
+pub const ENZYME_SCHEMA_VERSION: u32 = 1;
+
+const BUILTIN: &str = include_str!("../../data/enzymes.toml");
+
+/// A tRNA modification enzyme (or complex)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Enzyme {
+    /// Identifier, usually the best-known member's name (e.g. "TrmA")
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Short names of the modifications it catalyses
+    pub modifications: Vec<String>,
+    /// Gene names keyed by domain name or organism
+    #[serde(default)]
+    pub genes: BTreeMap<String, Vec<String>>,
+    /// Sequence context the enzyme recognizes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub determinants: Vec<ContextPredicate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl Enzyme {
+    /// Gene names for a domain name or organism (empty if not annotated)
+    pub fn genes_for(&self, taxon: &str) -> &[String] {
+        self.genes.get(taxon).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Gene names for a domain
+    pub fn genes_for_domain(&self, domain: Domain) -> &[String] {
+        self.genes_for(domain.name())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EnzymeTable {
+    schema_version: u32,
+    #[serde(default)]
+    enzymes: Vec<Enzyme>,
+}
+
+/// The bundled enzyme records
+pub fn builtin_enzymes() -> Vec<Enzyme> {
+    let table: EnzymeTable = toml::from_str(BUILTIN).expect("bundled enzyme table is valid TOML");
+    assert!(table.schema_version <= ENZYME_SCHEMA_VERSION);
+    table.enzymes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_enzymes() {
+        let enzymes = builtin_enzymes();
+        let trma = enzymes.iter().find(|e| e.id == "TrmA").unwrap();
+        assert_eq!(trma.genes_for_domain(Domain::Bacterial), ["trmA"]);
+        assert_eq!(trma.genes_for("Saccharomyces cerevisiae"), ["TRM2"]);
+        assert!(trma.genes_for_domain(Domain::Archaeal).is_empty());
+
+        for enzyme in &enzymes {
+            assert!(!enzyme.modifications.is_empty(), "{}", enzyme.id);
+            assert!(enzyme.determinants.iter().all(|d| d.problem().is_none()), "{}", enzyme.id);
+        }
+    }
+}
@@ -0,0 +1,377 @@
+//! Declarative position-expectation files
+//!
+//! Position expectations are kept in versioned TOML or JSON documents rather
+//! than Rust code, so curators can maintain organism-specific tables without
+//! recompiling. The built-in tables for each domain are bundled files in the
+//! same format (`data/expectations/*.toml`).
+
+use super::context::ContextPredicate;
+use super::database::ModificationDatabase;
+use super::sprinzl::SprinzlMapper;
+use super::taxonomy::Domain;
+use super::types::{
+    ConservationLevel, Editing, Evidence, FunctionalRole, Isotype, Modification, PositionModExpectation,
+    Prerequisite, SprinzlPosition,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Current version of the expectation file schema
+pub const EXPECTATION_SCHEMA_VERSION: u32 = 1;
+
+const EUKARYOTIC: &str = include_str!("../../data/expectations/eukaryotic.toml");
+const BACTERIAL: &str = include_str!("../../data/expectations/bacterial.toml");
+const ARCHAEAL: &str = include_str!("../../data/expectations/archaeal.toml");
+const PLASTID: &str = include_str!("../../data/expectations/plastid.toml");
+const MITOCHONDRIAL: &str = include_str!("../../data/expectations/mitochondrial.toml");
+
+/// A versioned set of position expectations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectationFile {
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub expectations: Vec<ExpectationRecord>,
+}
+
+/// One expectation entry, naming modifications by short name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectationRecord {
+    /// Sprinzl position (e.g. "34", "17a", "e12")
+    pub position: SprinzlPosition,
+    /// Short names of the expected modifications (alternatives)
+    pub modifications: Vec<String>,
+    pub conservation: ConservationLevel,
+    pub functional_role: FunctionalRole,
+    /// Isotypes this applies to (empty = all)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub isotypes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Context selecting the tRNAs this applies to (all must hold)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applies_when: Vec<ContextPredicate>,
+    /// Context the modifying enzyme needs (losing any is an incompatibility)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub requires: Vec<ContextPredicate>,
+    /// IDs of the writer enzymes (see `data/enzymes.toml`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub enzymes: Vec<String>,
+    /// Modifications at other sites that must be present first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<Prerequisite>,
+    /// Evidence code, references and organism scope
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<Evidence>,
+    /// Observed fraction (0-1) of reference tRNAs carrying the modification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency: Option<f64>,
+    /// Genomic and edited base, for edited sites (modifications may then be empty)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editing: Option<Editing>,
+}
+
+impl ExpectationFile {
+    /// The bundled expectation table of a domain
+    pub fn builtin(domain: Domain) -> Self {
+        let source = match domain {
+            Domain::Eukaryotic => EUKARYOTIC,
+            Domain::Bacterial => BACTERIAL,
+            Domain::Archaeal => ARCHAEAL,
+            Domain::Plastid => PLASTID,
+            Domain::Mitochondrial => MITOCHONDRIAL,
+        };
+        Self::from_toml_str(source).expect("bundled expectation table is valid TOML")
+    }
+
+    /// Read an expectation file; `.json` files are parsed as JSON, everything else as TOML
+    pub fn from_path(path: &Path) -> Result<Self, ExpectationError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| ExpectationError::IoError(format!("{}: {}", path.display(), e)))?;
+        if is_json(path) {
+            Self::from_json_str(&content)
+        } else {
+            Self::from_toml_str(&content)
+        }
+    }
+
+    pub fn from_toml_str(s: &str) -> Result<Self, ExpectationError> {
+        let file: Self = toml::from_str(s).map_err(|e| ExpectationError::ParseError(e.to_string()))?;
+        file.check_version()?;
+        Ok(file)
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self, ExpectationError> {
+        let file: Self = serde_json::from_str(s).map_err(|e| ExpectationError::ParseError(e.to_string()))?;
+        file.check_version()?;
+        Ok(file)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, ExpectationError> {
+        toml::to_string_pretty(self).map_err(|e| ExpectationError::ParseError(e.to_string()))
+    }
+
+    pub fn to_json_string(&self) -> Result<String, ExpectationError> {
+        serde_json::to_string_pretty(self).map_err(|e| ExpectationError::ParseError(e.to_string()))
+    }
+
+    /// Write the file as JSON or TOML depending on the extension
+    pub fn write(&self, path: &Path) -> Result<(), ExpectationError> {
+        let content = if is_json(path) {
+            self.to_json_string()?
+        } else {
+            self.to_toml_string()?
+        };
+        std::fs::write(path, content)
+            .map_err(|e| ExpectationError::IoError(format!("{}: {}", path.display(), e)))
+    }
+
+    fn check_version(&self) -> Result<(), ExpectationError> {
+        if self.schema_version == 0 || self.schema_version > EXPECTATION_SCHEMA_VERSION {
+            return Err(ExpectationError::UnsupportedVersion(self.schema_version));
+        }
+        Ok(())
+    }
+
+    /// Validate every record against a database and collect all problems
+    pub fn validate(&self, db: &ModificationDatabase) -> Vec<String> {
+        let mapper = SprinzlMapper::new_standard();
+        let mut problems = Vec::new();
+
+        for (i, record) in self.expectations.iter().enumerate() {
+            let entry = format!("expectation #{} (position {})", i + 1, record.position);
+
+            let in_range = record.position.base_number().map(|n| (1..=76).contains(&n)).unwrap_or(false);
+            if mapper.get_cm_index(&record.position).is_none() && !in_range {
+                problems.push(format!("{}: unknown Sprinzl position", entry));
+            }
+            match record.editing {
+                None if record.modifications.is_empty() => {
+                    problems.push(format!("{}: no modifications listed", entry));
+                }
+                Some(editing) if editing.from == editing.to => {
+                    problems.push(format!("{}: editing must change the base", entry));
+                }
+                _ => {}
+            }
+            for name in &record.modifications {
+                if db.get_modification(name).is_none() {
+                    problems.push(format!("{}: unknown modification '{}'", entry, name));
+                }
+            }
+            for isotype in &record.isotypes {
+                if !Isotype::ALL.contains(&isotype.as_str()) {
+                    problems.push(format!("{}: unknown isotype '{}'", entry, isotype));
+                }
+            }
+            for id in &record.enzymes {
+                match db.get_enzyme(id) {
+                    None => problems.push(format!("{}: unknown enzyme '{}'", entry, id)),
+                    Some(enzyme) => {
+                        let catalyses = record.modifications.is_empty() || record.modifications.iter().any(|name| {
+                            enzyme.modifications.iter().any(|m| db.same_modification(m, name))
+                        });
+                        if !catalyses {
+                            problems.push(format!(
+                                "{}: enzyme '{}' installs none of the listed modifications",
+                                entry, id
+                            ));
+                        }
+                    }
+                }
+            }
+            for prerequisite in &record.depends_on {
+                let expected = self.expectations.iter().any(|other| {
+                    other.position == prerequisite.position
+                        && other.modifications.iter().any(|m| db.same_modification(m, &prerequisite.modification))
+                });
+                if !expected {
+                    problems.push(format!(
+                        "{}: prerequisite {} has no expectation of its own",
+                        entry, prerequisite
+                    ));
+                }
+            }
+            if record.frequency.is_some_and(|f| !(0.0..=1.0).contains(&f)) {
+                problems.push(format!("{}: frequency must be between 0 and 1", entry));
+            }
+            if let Some(problem) = record.evidence.as_ref().and_then(Evidence::problem) {
+                problems.push(format!("{}: {}", entry, problem));
+            }
+            for predicate in record.applies_when.iter().chain(&record.requires) {
+                if let Some(problem) = predicate.problem() {
+                    problems.push(format!("{}: {}", entry, problem));
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// Resolve records into expectations using a database's modifications
+    ///
+    /// Strict resolution fails on any validation problem. Lenient resolution
+    /// (used for the bundled tables over partial MODOMICS snapshots) drops
+    /// modifications the database does not have, and records left with none.
+    pub fn resolve(
+        &self,
+        db: &ModificationDatabase,
+        strict: bool,
+    ) -> Result<Vec<PositionModExpectation>, ExpectationError> {
+        if strict {
+            let problems = self.validate(db);
+            if !problems.is_empty() {
+                return Err(ExpectationError::Invalid(problems));
+            }
+        }
+
+        let mut expectations = Vec::new();
+        for record in &self.expectations {
+            let mut modifications = Vec::new();
+            for name in &record.modifications {
+                if let Some(m) = db.get_modification(name) {
+                    if !modifications.iter().any(|existing: &Modification| existing.short_name == m.short_name) {
+                        modifications.push(m.clone());
+                    }
+                }
+            }
+            if modifications.is_empty() && (record.editing.is_none() || !record.modifications.is_empty()) {
+                continue;
+            }
+
+            expectations.push(PositionModExpectation {
+                position: record.position.clone(),
+                modifications,
+                conservation: record.conservation,
+                functional_role: record.functional_role,
+                isotypes: record.isotypes.clone(),
+                notes: record.notes.clone(),
+                applies_when: record.applies_when.clone(),
+                requires: record.requires.clone(),
+                enzymes: record
+                    .enzymes
+                    .iter()
+                    .filter(|id| db.get_enzyme(id).is_some())
+                    .cloned()
+                    .collect(),
+                depends_on: record.depends_on.clone(),
+                evidence: record.evidence.clone(),
+                frequency: record.frequency,
+                editing: record.editing,
+            });
+        }
+
+        Ok(expectations)
+    }
+}
+
+impl From<&PositionModExpectation> for ExpectationRecord {
+    fn from(exp: &PositionModExpectation) -> Self {
+        Self {
+            position: exp.position.clone(),
+            modifications: exp.modifications.iter().map(|m| m.short_name.clone()).collect(),
+            conservation: exp.conservation,
+            functional_role: exp.functional_role,
+            isotypes: exp.isotypes.clone(),
+            notes: exp.notes.clone(),
+            applies_when: exp.applies_when.clone(),
+            requires: exp.requires.clone(),
+            enzymes: exp.enzymes.clone(),
+            depends_on: exp.depends_on.clone(),
+            evidence: exp.evidence.clone(),
+            frequency: exp.frequency,
+            editing: exp.editing,
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false)
+}
+
+/// Errors from loading expectation files
+#[derive(Debug)]
+pub enum ExpectationError {
+    IoError(String),
+    ParseError(String),
+    UnsupportedVersion(u32),
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ExpectationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpectationError::IoError(e) => write!(f, "IO error: {}", e),
+            ExpectationError::ParseError(e) => write!(f, "Parse error: {}", e),
+            ExpectationError::UnsupportedVersion(v) => write!(
+                f,
+                "Unsupported expectation schema version {} (this build reads up to {})",
+                v, EXPECTATION_SCHEMA_VERSION
+            ),
+            ExpectationError::Invalid(problems) => {
+                write!(f, "Invalid expectation file:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpectationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_tables_validate() {
+        for domain in Domain::ALL {
+            let db = ModificationDatabase::for_domain(domain);
+            let problems = ExpectationFile::builtin(domain).validate(&db);
+            assert!(problems.is_empty(), "{}: {:?}", domain, problems);
+        }
+    }
+
+    #[test]
+    fn test_validation_reports_problems() {
+        let toml = r#"
+            schema_version = 1
+
+            [[expectations]]
+            position = "99"
+            modifications = ["m1A", "notAMod"]
+            conservation = "Rare"
+            functional_role = "Unknown"
+            isotypes = ["Xyz"]
+        "#;
+        let file = ExpectationFile::from_toml_str(toml).unwrap();
+        let db = ModificationDatabase::eukaryotic();
+
+        let err = file.resolve(&db, true).unwrap_err();
+        let ExpectationError::Invalid(problems) = err else {
+            panic!("expected validation error");
+        };
+        assert_eq!(problems.len(), 3);
+
+        // Lenient resolution keeps what it can
+        let resolved = file.resolve(&db, false).unwrap();
+        assert_eq!(resolved[0].modifications.len(), 1);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let json = r#"{"schema_version": 99, "expectations": []}"#;
+        assert!(matches!(
+            ExpectationFile::from_json_str(json),
+            Err(ExpectationError::UnsupportedVersion(99))
+        ));
+    }
+}
@@ -0,0 +1,269 @@
+//! Position expectations learned from modified reference sequences
+//!
+//! Counts, for each isotype and Sprinzl position, how often each modification
+//! is found in reference tRNAs of one domain (see [`observed`]). A site found
+//! in every isotype with enough data becomes one expectation for all tRNAs;
+//! otherwise each isotype gets its own. Every learned expectation carries its
+//! observed frequency, which sets the severity of losing it.
+//!
+//! [`observed`]: super::observed
+
+use super::expectations::{ExpectationFile, ExpectationRecord, EXPECTATION_SCHEMA_VERSION};
+use super::observed::{ModifiedSequence, Residue};
+use super::sprinzl::SprinzlMapper;
+use super::taxonomy::Domain;
+use super::types::{ConservationLevel, Evidence, EvidenceCode, FunctionalRole, Isotype, SprinzlPosition};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Thresholds for emitting a learned expectation
+#[derive(Debug, Clone)]
+pub struct LearnOptions {
+    /// Reference tRNAs needed at a position before a frequency is trusted
+    pub min_records: usize,
+    /// Frequencies below this are dropped
+    pub min_frequency: f64,
+}
+
+impl Default for LearnOptions {
+    fn default() -> Self {
+        Self {
+            min_records: 3,
+            min_frequency: 0.05,
+        }
+    }
+}
+
+impl LearnOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_min_records(mut self, min_records: usize) -> Self {
+        self.min_records = min_records;
+        self
+    }
+
+    pub fn with_min_frequency(mut self, min_frequency: f64) -> Self {
+        self.min_frequency = min_frequency;
+        self
+    }
+}
+
+/// A learned expectation table and the records behind it
+#[derive(Debug, Clone)]
+pub struct LearnedTable {
+    pub file: ExpectationFile,
+    /// Records numbered and counted
+    pub records_used: usize,
+    /// Records from organisms of another domain
+    pub other_domain: Vec<String>,
+    /// Records whose organism could not be placed in a domain
+    pub unplaced: Vec<String>,
+    /// Records that could not be numbered in Sprinzl coordinates
+    pub unaligned: Vec<String>,
+}
+
+/// Tally of one site: tRNAs covering it and, per modification, those carrying it
+#[derive(Default)]
+struct SiteCounts {
+    total: usize,
+    carried: BTreeMap<String, (usize, BTreeSet<String>)>,
+}
+
+impl SiteCounts {
+    fn add(&mut self, residue: &Residue, organism: &str) {
+        self.total += 1;
+        if let Residue::Modified { modification, .. } = residue {
+            let (count, organisms) = self.carried.entry(modification.clone()).or_default();
+            *count += 1;
+            organisms.insert(organism.to_string());
+        }
+    }
+
+    fn frequency(&self, modification: &str) -> f64 {
+        let carried = self.carried.get(modification).map_or(0, |(count, _)| *count);
+        carried as f64 / self.total as f64
+    }
+}
+
+/// Learn a domain's expectation table from modified reference sequences
+///
+/// Records are assigned to domains by organism; organelle tables cannot be
+/// told apart by organism, so for those every record is used.
+pub fn learn_expectations(records: &[ModifiedSequence], domain: Domain, options: &LearnOptions) -> LearnedTable {
+    let mut table = LearnedTable {
+        file: ExpectationFile {
+            schema_version: EXPECTATION_SCHEMA_VERSION,
+            name: Some(format!("{} (learned)", domain)),
+            description: None,
+            expectations: Vec::new(),
+        },
+        records_used: 0,
+        other_domain: Vec::new(),
+        unplaced: Vec::new(),
+        unaligned: Vec::new(),
+    };
+
+    let organelle = matches!(domain, Domain::Mitochondrial | Domain::Plastid);
+    // Keyed by Sprinzl position label; sorted into 5'-3' order at the end
+    let mut pooled: BTreeMap<String, SiteCounts> = BTreeMap::new();
+    let mut by_isotype: BTreeMap<(String, String), SiteCounts> = BTreeMap::new();
+
+    for record in records {
+        if !organelle {
+            match Domain::from_organism(&record.organism) {
+                Some(d) if d == domain => {}
+                Some(_) => {
+                    table.other_domain.push(record.id.clone());
+                    continue;
+                }
+                None => {
+                    table.unplaced.push(record.id.clone());
+                    continue;
+                }
+            }
+        }
+        let Some(mapping) = record.sprinzl_map() else {
+            table.unaligned.push(record.id.clone());
+            continue;
+        };
+        table.records_used += 1;
+
+        let isotype = record.isotype.as_deref().filter(|i| Isotype::ALL.contains(i));
+        for (position, idx) in mapping {
+            let Some(residue) = record.residues.get(idx).filter(|r| r.base().is_some()) else {
+                continue;
+            };
+            pooled.entry(position.0.clone()).or_default().add(residue, &record.organism);
+            if let Some(isotype) = isotype {
+                by_isotype.entry((position.0, isotype.to_string())).or_default().add(residue, &record.organism);
+            }
+        }
+    }
+
+    for (label, site) in &pooled {
+        let position = SprinzlPosition::new(label.as_str());
+        let isotype_sites: Vec<(&String, &SiteCounts)> = by_isotype
+            .iter()
+            .filter(|((p, _), counts)| p == label && counts.total >= options.min_records)
+            .map(|((_, isotype), counts)| (isotype, counts))
+            .collect();
+
+        for (modification, (_, organisms)) in &site.carried {
+            let everywhere = isotype_sites.iter().all(|(_, counts)| counts.carried.contains_key(modification));
+            if everywhere && site.total >= options.min_records {
+                let frequency = site.frequency(modification);
+                if frequency >= options.min_frequency {
+                    table.file.expectations.push(learned_record(&position, modification, None, frequency, site, organisms));
+                }
+                continue;
+            }
+            for (isotype, counts) in &isotype_sites {
+                let frequency = counts.frequency(modification);
+                if let Some((_, organisms)) = counts.carried.get(modification).filter(|_| frequency >= options.min_frequency) {
+                    table.file.expectations.push(learned_record(&position, modification, Some(isotype), frequency, counts, organisms));
+                }
+            }
+        }
+    }
+
+    let mapper = SprinzlMapper::new_standard();
+    table.file.expectations.sort_by(|a, b| {
+        (mapper.sort_key(&a.position), &a.isotypes, &a.modifications)
+            .cmp(&(mapper.sort_key(&b.position), &b.isotypes, &b.modifications))
+    });
+    table.file.description = Some(format!(
+        "Learned from {} reference tRNAs (min {} per site, min frequency {})",
+        table.records_used, options.min_records, options.min_frequency
+    ));
+    table
+}
+
+fn learned_record(
+    position: &SprinzlPosition,
+    modification: &str,
+    isotype: Option<&str>,
+    frequency: f64,
+    site: &SiteCounts,
+    organisms: &BTreeSet<String>,
+) -> ExpectationRecord {
+    let conservation = match (isotype, frequency) {
+        (_, f) if f < 0.5 => ConservationLevel::Rare,
+        (Some(_), _) => ConservationLevel::IsotypeSpecific,
+        (None, f) if f >= 0.9 => ConservationLevel::Universal,
+        (None, _) => ConservationLevel::DomainSpecific,
+    };
+    let functional_role = match position.base_number() {
+        Some(34..=37) => FunctionalRole::AnticodonFunction,
+        _ => FunctionalRole::Unknown,
+    };
+    let carried = site.carried.get(modification).map_or(0, |(count, _)| *count);
+
+    ExpectationRecord {
+        position: position.clone(),
+        modifications: vec![modification.to_string()],
+        conservation,
+        functional_role,
+        isotypes: isotype.into_iter().map(str::to_string).collect(),
+        notes: Some(format!("Observed in {} of {} reference tRNAs", carried, site.total)),
+        applies_when: Vec::new(),
+        requires: Vec::new(),
+        enzymes: Vec::new(),
+        depends_on: Vec::new(),
+        evidence: Some(Evidence {
+            code: EvidenceCode::DirectSequencing,
+            references: Vec::new(),
+            organisms: organisms.iter().cloned().collect(),
+        }),
+        frequency: Some((frequency * 1000.0).round() / 1000.0),
+        editing: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::{parse_sequence_fasta, ModificationDatabase};
+
+    const PHE: &str = "GCGGAUUUAGCUCAGDDGGGAGAGCGCCAGACUGAAGAUCUGGAGGUCCUGUGTΨCGAUCCACAGAAUUCGCACCA";
+
+    #[test]
+    fn test_learn_frequencies() {
+        let db = ModificationDatabase::eukaryotic();
+        // Four yeast tRNAs: Psi55 everywhere, D16 in half of them (Phe only),
+        // plus an E. coli record that belongs to another domain
+        let without_d = PHE.replacen("DD", "UU", 1);
+        let mut fasta = String::new();
+        for (i, (isotype, seq)) in [("Phe", PHE), ("Phe", PHE), ("Ala", without_d.as_str()), ("Ala", without_d.as_str())]
+            .iter()
+            .enumerate()
+        {
+            fasta.push_str(&format!(">y{}|Saccharomyces cerevisiae|{}\n{}\n", i, isotype, seq));
+        }
+        fasta.push_str(&format!(">e1|Escherichia coli|Phe\n{}\n", PHE));
+        let records = parse_sequence_fasta(&fasta, &db).unwrap();
+
+        let learned = learn_expectations(&records, Domain::Eukaryotic, &LearnOptions::new().with_min_records(2));
+        assert_eq!(learned.records_used, 4);
+        assert_eq!(learned.other_domain, ["e1"]);
+
+        let find = |pos: &str, name: &str| learned.file.expectations.iter()
+            .find(|r| r.position.0 == pos && r.modifications == [name])
+            .unwrap();
+        let psi = find("55", "Psi");
+        assert!(psi.isotypes.is_empty());
+        assert_eq!(psi.frequency, Some(1.0));
+        assert_eq!(psi.conservation, ConservationLevel::Universal);
+
+        let d = find("16", "D");
+        assert_eq!(d.isotypes, ["Phe"]);
+        assert_eq!(d.frequency, Some(1.0));
+        assert_eq!(d.conservation, ConservationLevel::IsotypeSpecific);
+
+        // The learned table loads and its frequencies survive resolution
+        let mut db = db;
+        db.replace_expectations(&learned.file).unwrap();
+        let exp = db.get_expectations(&SprinzlPosition::from_num(55));
+        assert_eq!(exp[0].frequency, Some(1.0));
+    }
+}
@@ -0,0 +1,296 @@
+//! Layering curated modifications over a MODOMICS snapshot
+//!
+//! The built-in catalogue is curated: it uses our names ("Psi"), carries ChEBI
+//! IDs and states what a modification reads as in sequencing data. MODOMICS is
+//! the reference for everything else. When both have a modification:
+//!
+//! - names, codes, parent and genomic bases, incompatible bases and the ChEBI
+//!   ID come from the curated entry (a missing ChEBI ID is taken from MODOMICS);
+//! - the MODOMICS ID, abbreviation and chemical properties come from MODOMICS;
+//!   a curated one-character code that MODOMICS replaces is kept as an
+//!   alternate code, so sequences written with it still decode.
+//!
+//! Fields both layers set to different values are reported as conflicts, and
+//! every field of every modification records which layer it came from.
+
+use super::modomics::{ModomicsImport, SkippedEntry};
+use super::types::{ModCode, Modification};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Fields of [`Modification`] tracked by provenance
+pub const MODIFICATION_FIELDS: [&str; 11] = [
+    "name",
+    "short_name",
+    "code",
+    "alt_codes",
+    "parent_base",
+    "genomic_expectation",
+    "incompatible_bases",
+    "chebi_id",
+    "modomics_unicode",
+    "modomics_id",
+    "chemistry",
+];
+
+/// Layer a field value came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Curated,
+    Modomics,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Curated => write!(f, "curated"),
+            Source::Modomics => write!(f, "MODOMICS"),
+        }
+    }
+}
+
+/// Source of each field of a modification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Provenance(BTreeMap<String, Source>);
+
+impl Provenance {
+    /// Every field from one layer
+    pub fn uniform(source: Source) -> Self {
+        Self(MODIFICATION_FIELDS.iter().map(|f| (f.to_string(), source)).collect())
+    }
+
+    /// Source of a field, by [`Modification`] field name
+    pub fn source(&self, field: &str) -> Option<Source> {
+        self.0.get(field).copied()
+    }
+
+    /// Fields and their sources, by field name
+    pub fn fields(&self) -> impl Iterator<Item = (&str, Source)> {
+        self.0.iter().map(|(field, source)| (field.as_str(), *source))
+    }
+
+    fn set(&mut self, field: &str, source: Source) {
+        self.0.insert(field.to_string(), source);
+    }
+}
+
+/// A field on which the curated and MODOMICS entries disagree
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    /// Short name of the merged modification
+    pub modification: String,
+    pub field: String,
+    pub curated: String,
+    pub modomics: String,
+    /// The layer whose value was kept
+    pub kept: Source,
+}
+
+/// What happened when a MODOMICS snapshot was merged
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeReport {
+    /// MODOMICS entries left out, and why
+    pub skipped: Vec<SkippedEntry>,
+    pub conflicts: Vec<MergeConflict>,
+    /// Curated modifications matched to a MODOMICS entry
+    pub merged: Vec<String>,
+    /// Curated modifications missing from the snapshot
+    pub curated_only: Vec<String>,
+    /// Snapshot modifications with no curated entry
+    pub modomics_only: Vec<String>,
+}
+
+/// A merged catalogue
+pub(crate) struct Layered {
+    pub modifications: FxHashMap<String, Modification>,
+    pub provenance: FxHashMap<String, Provenance>,
+    pub report: MergeReport,
+}
+
+/// Layer curated modifications over a MODOMICS import
+///
+/// A curated entry matches the MODOMICS entry with its short name, or with a
+/// synonym of it from `synonyms` (e.g. "Psi" and "Y").
+pub(crate) fn merge(
+    curated: Vec<Modification>,
+    import: ModomicsImport,
+    synonyms: &[&[&str]],
+) -> Layered {
+    let ModomicsImport { modifications: mut snapshot, skipped } = import;
+    let mut layered = Layered {
+        modifications: FxHashMap::default(),
+        provenance: FxHashMap::default(),
+        report: MergeReport { skipped, ..MergeReport::default() },
+    };
+
+    for curated in curated {
+        let group = synonyms.iter().find(|g| g.contains(&curated.short_name.as_str()));
+        let key = std::iter::once(curated.short_name.as_str())
+            .chain(group.into_iter().flat_map(|g| g.iter().copied()))
+            .find(|name| snapshot.contains_key(*name))
+            .map(str::to_string);
+
+        let name = curated.short_name.clone();
+        let (modification, provenance) = match key.and_then(|k| snapshot.remove(&k)) {
+            Some(modomics) => {
+                layered.report.merged.push(name.clone());
+                layer(curated, modomics, &mut layered.report.conflicts)
+            }
+            None => {
+                layered.report.curated_only.push(name.clone());
+                (curated, Provenance::uniform(Source::Curated))
+            }
+        };
+        layered.modifications.insert(name.clone(), modification);
+        layered.provenance.insert(name, provenance);
+    }
+
+    for (name, modification) in snapshot {
+        layered.report.modomics_only.push(name.clone());
+        layered.modifications.insert(name.clone(), modification);
+        layered.provenance.insert(name, Provenance::uniform(Source::Modomics));
+    }
+
+    layered.report.merged.sort();
+    layered.report.curated_only.sort();
+    layered.report.modomics_only.sort();
+    layered.report.conflicts.sort_by(|a, b| (&a.modification, &a.field).cmp(&(&b.modification, &b.field)));
+    layered
+}
+
+/// Merge one curated entry with its MODOMICS counterpart
+fn layer(
+    mut curated: Modification,
+    modomics: Modification,
+    conflicts: &mut Vec<MergeConflict>,
+) -> (Modification, Provenance) {
+    let mut provenance = Provenance::uniform(Source::Curated);
+    let mut conflict = |field: &str, curated_value: String, modomics_value: String, kept| {
+        conflicts.push(MergeConflict {
+            modification: curated.short_name.clone(),
+            field: field.to_string(),
+            curated: curated_value,
+            modomics: modomics_value,
+            kept,
+        });
+    };
+
+    if !curated.name.eq_ignore_ascii_case(&modomics.name) {
+        conflict("name", curated.name.clone(), modomics.name.clone(), Source::Curated);
+    }
+    if curated.parent_base != modomics.parent_base {
+        conflict(
+            "parent_base",
+            curated.parent_base.to_string(),
+            modomics.parent_base.to_string(),
+            Source::Curated,
+        );
+    }
+
+    match (curated.chebi_id, modomics.chebi_id) {
+        (Some(ours), Some(theirs)) if ours != theirs => {
+            conflict("chebi_id", ours.to_string(), theirs.to_string(), Source::Curated);
+        }
+        (None, Some(theirs)) => {
+            curated.chebi_id = Some(theirs);
+            provenance.set("chebi_id", Source::Modomics);
+        }
+        _ => {}
+    }
+
+    if let Some(theirs) = modomics.modomics_unicode {
+        if let Some(ours) = curated.modomics_unicode.filter(|ours| *ours != theirs) {
+            conflict("modomics_unicode", ours.to_string(), theirs.to_string(), Source::Modomics);
+            let code = ModCode::Unicode(ours);
+            if curated.code != code && !curated.alt_codes.contains(&code) {
+                curated.alt_codes.push(code);
+            }
+        }
+        curated.modomics_unicode = Some(theirs);
+        provenance.set("modomics_unicode", Source::Modomics);
+    }
+
+    curated.modomics_id = modomics.modomics_id;
+    curated.chemistry = modomics.chemistry;
+    provenance.set("modomics_id", Source::Modomics);
+    provenance.set("chemistry", Source::Modomics);
+
+    (curated, provenance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::modomics::import_modomics_json;
+    use crate::RnaBase;
+
+    const SNAPSHOT: &str = r#"{
+        "118": {"id": 118, "name": "pseudouridine-5'-monophosphate", "short_name": "Y", "new_abbrev": "P",
+                "reference_moiety": ["U"], "formula": "C9H12N2O6"},
+        "25": {"id": 25, "name": "1-methyladenosine", "short_name": "m1A", "reference_moiety": ["A"]},
+        "140": {"id": 140, "name": "unknown nucleotide", "short_name": "N", "reference_moiety": ["A", "C", "G", "U"]},
+        "422": {"id": 422, "name": "peroxywybutosine-5'-monophosphate", "short_name": "po2yW", "reference_moiety": ["QtRNA"]}
+    }"#;
+
+    fn layered() -> Layered {
+        let psi = Modification {
+            name: "pseudouridine".to_string(),
+            short_name: "Psi".to_string(),
+            code: ModCode::Unicode('Ψ'),
+            alt_codes: vec![ModCode::SingleChar('Y')],
+            parent_base: RnaBase::U,
+            genomic_expectation: RnaBase::U,
+            incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
+            chebi_id: Some(17802),
+            modomics_unicode: None,
+            modomics_id: None,
+            chemistry: None,
+        };
+        merge(vec![psi], import_modomics_json(SNAPSHOT).unwrap(), &[&["Psi", "Y"]])
+    }
+
+    #[test]
+    fn test_curated_entry_wins() {
+        let layered = layered();
+        let merged = &layered.modifications["Psi"];
+        assert!(!layered.modifications.contains_key("Y"));
+        assert_eq!(merged.name, "pseudouridine");
+        assert_eq!(merged.chebi_id, Some(17802));
+        assert_eq!(merged.modomics_id, Some(118));
+
+        let provenance = &layered.provenance["Psi"];
+        assert_eq!(provenance.fields().count(), MODIFICATION_FIELDS.len());
+        assert_eq!(provenance.source("name"), Some(Source::Curated));
+        assert_eq!(provenance.source("chebi_id"), Some(Source::Curated));
+        assert_eq!(provenance.source("modomics_id"), Some(Source::Modomics));
+        assert_eq!(provenance.source("chemistry"), Some(Source::Modomics));
+
+        let report = &layered.report;
+        assert_eq!(report.merged, ["Psi"]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!((report.conflicts[0].field.as_str(), report.conflicts[0].kept), ("name", Source::Curated));
+    }
+
+    #[test]
+    fn test_modomics_only_entry_added() {
+        let layered = layered();
+        assert_eq!(layered.report.modomics_only, ["m1A"]);
+        assert_eq!(layered.modifications["m1A"].parent_base, RnaBase::A);
+        assert_eq!(layered.provenance["m1A"], Provenance::uniform(Source::Modomics));
+    }
+
+    #[test]
+    fn test_skipped_entries_reported() {
+        // Entries that cannot be placed on one canonical base
+        let layered = layered();
+        let skipped: Vec<(u32, String)> =
+            layered.report.skipped.iter().map(|s| (s.id, s.reason.to_string())).collect();
+        assert_eq!(skipped, [
+            (140, "multiple parents (A/C/G/U)".to_string()),
+            (422, "non-canonical parent 'QtRNA'".to_string()),
+        ]);
+        assert!(!layered.modifications.contains_key("po2yW"));
+    }
+}
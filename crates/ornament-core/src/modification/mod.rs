@@ -4,8 +4,38 @@ pub mod types;
 pub mod database;
 pub mod sprinzl;
 pub mod modomics;
+pub mod expectations;
+pub mod taxonomy;
+pub mod context;
+pub mod enzyme;
+pub mod signature;
+pub mod merge;
+pub mod resolver;
+pub mod snapshot;
+pub mod diff;
+pub mod observed;
+pub mod learn;
 
 pub use types::*;
 pub use database::ModificationDatabase;
 pub use sprinzl::SprinzlMapper;
-pub use modomics::{parse_modomics_file, parse_modomics_json, ModomicsError};
+pub use modomics::{
+    import_modomics_file, import_modomics_json, parse_modomics_file, parse_modomics_json, ModomicsError,
+    ModomicsImport, SkipReason, SkippedEntry,
+};
+pub use taxonomy::{Domain, DomainError};
+pub use expectations::{ExpectationFile, ExpectationRecord, ExpectationError};
+pub use context::{ContextPredicate, PairState};
+pub use enzyme::Enzyme;
+pub use signature::{Propensity, SequencingSignature};
+pub use merge::{MergeConflict, MergeReport, Provenance, Source};
+pub use resolver::{CodeKind, ResolveError};
+pub use diff::{
+    diff_modifications, odd_status_changes, AffectedExpectation, ChangeKind, ModomicsDiff, OddStatusChange,
+};
+pub use observed::{
+    decode_residue, parse_sequence_fasta, parse_sequence_json, ModifiedSequence, ObservedModification, ObservedModifications, Residue,
+    SequenceError,
+};
+pub use learn::{learn_expectations, LearnOptions, LearnedTable};
+pub use snapshot::{DatabaseSnapshot, SnapshotContents, SnapshotError, SNAPSHOT_SCHEMA_VERSION};
@@ -3,9 +3,9 @@
 //! Parses modification data from the MODOMICS REST API JSON format.
 //! Download data from: https://genesilico.pl/modomics/api/modifications
 
-use super::types::{Modification, ModCode, RnaBase};
+use super::types::{ChemicalProperties, Modification, ModCode, RnaBase};
 use rustc_hash::FxHashMap;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 /// Raw MODOMICS modification entry from JSON
@@ -23,42 +23,129 @@ pub struct ModomicsEntry {
     #[serde(default)]
     pub mass_avg: Option<f64>,
     #[serde(default)]
+    pub mass_monoiso: Option<f64>,
+    #[serde(default)]
+    pub mass_prot: Option<f64>,
+    #[serde(default)]
     pub smile: Option<String>,
+    /// MS/MS product ions, "/"-separated m/z values
+    #[serde(default)]
+    pub product_ions: Option<String>,
+    #[serde(default)]
+    pub lc_elution_time: Option<String>,
+    #[serde(default)]
+    pub lc_elution_comment: Option<String>,
+}
+
+/// Modifications parsed from a MODOMICS snapshot, and the entries left out
+#[derive(Debug, Clone, Default)]
+pub struct ModomicsImport {
+    /// Modifications indexed by short name
+    pub modifications: FxHashMap<String, Modification>,
+    /// Entries that could not be used, in ID order
+    pub skipped: Vec<SkippedEntry>,
+}
+
+/// A MODOMICS entry left out of the modification catalogue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SkippedEntry {
+    pub id: u32,
+    pub name: String,
+    pub short_name: String,
+    pub reason: SkipReason,
+}
+
+/// Why a MODOMICS entry was left out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// No short name to index it by
+    MissingShortName,
+    /// Several reference moieties (caps and terminal groups on any nucleotide)
+    MultipleParents(Vec<String>),
+    /// Reference moiety is not A, C, G or U (e.g. "X", "QtRNA")
+    NonCanonicalParent(String),
+    /// Another entry, with this ID, already has the short name
+    DuplicateShortName(u32),
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkipReason::MissingShortName => write!(f, "no short name"),
+            SkipReason::MultipleParents(parents) => write!(f, "multiple parents ({})", parents.join("/")),
+            SkipReason::NonCanonicalParent(parent) => write!(f, "non-canonical parent '{}'", parent),
+            SkipReason::DuplicateShortName(id) => write!(f, "short name already used by entry {}", id),
+        }
+    }
 }
 
 /// Parse MODOMICS JSON file into modification entries
 pub fn parse_modomics_file(path: &Path) -> Result<FxHashMap<String, Modification>, ModomicsError> {
-    let content = std::fs::read_to_string(path)
-        .map_err(|e| ModomicsError::IoError(e.to_string()))?;
-    parse_modomics_json(&content)
+    import_modomics_file(path).map(|import| import.modifications)
 }
 
 /// Parse MODOMICS JSON string into modification entries
 pub fn parse_modomics_json(json: &str) -> Result<FxHashMap<String, Modification>, ModomicsError> {
+    import_modomics_json(json).map(|import| import.modifications)
+}
+
+/// Parse a MODOMICS JSON file, reporting the entries that were skipped
+pub fn import_modomics_file(path: &Path) -> Result<ModomicsImport, ModomicsError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| ModomicsError::IoError(e.to_string()))?;
+    import_modomics_json(&content)
+}
+
+/// Parse a MODOMICS JSON string, reporting the entries that were skipped
+pub fn import_modomics_json(json: &str) -> Result<ModomicsImport, ModomicsError> {
     // MODOMICS returns a map with string keys (modification IDs)
     let raw: FxHashMap<String, ModomicsEntry> = serde_json::from_str(json)
         .map_err(|e| ModomicsError::ParseError(e.to_string()))?;
 
-    let mut modifications = FxHashMap::default();
+    // ID order, so the lowest ID wins a duplicated short name
+    let mut entries: Vec<ModomicsEntry> = raw.into_values().collect();
+    entries.sort_by_key(|entry| entry.id);
+
+    let mut import = ModomicsImport::default();
 
-    for (_id, entry) in raw {
-        if let Some(modification) = convert_entry(&entry) {
-            modifications.insert(modification.short_name.clone(), modification);
+    for entry in entries {
+        let converted = match import.modifications.get(&entry.short_name) {
+            Some(existing) => Err(SkipReason::DuplicateShortName(existing.modomics_id.unwrap_or_default())),
+            None => convert_entry(&entry),
+        };
+        match converted {
+            Ok(modification) => {
+                import.modifications.insert(modification.short_name.clone(), modification);
+            }
+            Err(reason) => import.skipped.push(SkippedEntry {
+                id: entry.id,
+                name: entry.name,
+                short_name: entry.short_name,
+                reason,
+            }),
         }
     }
 
-    Ok(modifications)
+    Ok(import)
 }
 
 /// Convert a MODOMICS entry to our Modification type
-fn convert_entry(entry: &ModomicsEntry) -> Option<Modification> {
+fn convert_entry(entry: &ModomicsEntry) -> Result<Modification, SkipReason> {
+    if entry.short_name.trim().is_empty() {
+        return Err(SkipReason::MissingShortName);
+    }
+
     // Determine parent base from reference_moiety
+    if entry.reference_moiety.len() > 1 {
+        return Err(SkipReason::MultipleParents(entry.reference_moiety.clone()));
+    }
     let parent_base = match entry.reference_moiety.first().map(|s| s.as_str()) {
         Some("A") => RnaBase::A,
         Some("C") => RnaBase::C,
         Some("G") => RnaBase::G,
         Some("U") => RnaBase::U,
-        _ => return None, // Skip entries without clear parent base (X, QtRNA, etc.)
+        other => return Err(SkipReason::NonCanonicalParent(other.unwrap_or_default().to_string())),
     };
 
     // Determine incompatible bases (all bases except parent)
@@ -90,7 +177,7 @@ fn convert_entry(entry: &ModomicsEntry) -> Option<Modification> {
         }
     }
 
-    Some(Modification {
+    Ok(Modification {
         name: entry.name.clone(),
         short_name: entry.short_name.clone(),
         code,
@@ -100,9 +187,29 @@ fn convert_entry(entry: &ModomicsEntry) -> Option<Modification> {
         incompatible_bases,
         chebi_id: None, // MODOMICS doesn't include ChEBI in basic API
         modomics_unicode,
+        modomics_id: Some(entry.id),
+        chemistry: Some(chemistry(entry)),
     })
 }
 
+/// Collect an entry's chemical properties (MODOMICS uses "" for missing text)
+fn chemistry(entry: &ModomicsEntry) -> ChemicalProperties {
+    let text = |s: &Option<String>| s.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(String::from);
+
+    ChemicalProperties {
+        formula: text(&entry.formula),
+        mass_avg: entry.mass_avg,
+        mass_monoiso: entry.mass_monoiso,
+        mass_prot: entry.mass_prot,
+        smiles: text(&entry.smile),
+        product_ions: entry.product_ions.as_deref()
+            .map(|ions| ions.split('/').filter_map(|ion| ion.trim().parse().ok()).collect())
+            .unwrap_or_default(),
+        lc_elution_time: text(&entry.lc_elution_time),
+        lc_elution_comment: text(&entry.lc_elution_comment),
+    }
+}
+
 /// Errors from MODOMICS parsing
 #[derive(Debug)]
 pub enum ModomicsError {
@@ -150,7 +257,13 @@ mod tests {
                 "short_name": "Y",
                 "new_abbrev": "P",
                 "reference_moiety": ["U"],
-                "formula": "C9H12N2O6"
+                "formula": "C9H12N2O6",
+                "mass_avg": 244.201,
+                "mass_monoiso": 244.0695,
+                "mass_prot": 245.0773,
+                "product_ions": "209/179/155",
+                "lc_elution_time": "",
+                "lc_elution_comment": null
             }
         }"#;
 
@@ -162,6 +275,32 @@ mod tests {
         assert_eq!(psi.parent_base, RnaBase::U);
         assert!(psi.incompatible_bases.contains(&RnaBase::A));
         assert!(!psi.incompatible_bases.contains(&RnaBase::U));
+
+        assert_eq!(psi.modomics_id, Some(118));
+        let chemistry = psi.chemistry.as_ref().unwrap();
+        assert_eq!(chemistry.formula.as_deref(), Some("C9H12N2O6"));
+        assert_eq!(chemistry.mass_monoiso, Some(244.0695));
+        assert_eq!(chemistry.product_ions, [209.0, 179.0, 155.0]);
+        assert_eq!(chemistry.lc_elution_time, None);
+    }
+
+    #[test]
+    fn test_import_reports_skipped_entries() {
+        let json = r#"{
+            "14": {"id": 14, "name": "dihydrouridine", "short_name": "D", "reference_moiety": ["U"]},
+            "140": {"id": 140, "name": "unknown nucleotide", "short_name": "N", "reference_moiety": ["A", "C", "G", "U"]},
+            "311": {"id": 311, "name": "2'-deoxycytidine-5'-monophosphate", "short_name": "", "reference_moiety": ["C"]},
+            "422": {"id": 422, "name": "peroxywybutosine-5'-monophosphate", "short_name": "po2yW", "reference_moiety": ["QtRNA"]}
+        }"#;
+
+        let import = import_modomics_json(json).unwrap();
+        assert_eq!(import.modifications.len(), 1);
+        let reasons: Vec<(u32, String)> = import.skipped.iter().map(|s| (s.id, s.reason.to_string())).collect();
+        assert_eq!(reasons, [
+            (140, "multiple parents (A/C/G/U)".to_string()),
+            (311, "no short name".to_string()),
+            (422, "non-canonical parent 'QtRNA'".to_string()),
+        ]);
     }
 
     #[test]
@@ -175,9 +314,14 @@ mod tests {
             reference_moiety: vec!["X".to_string()],
             formula: None,
             mass_avg: None,
+            mass_monoiso: None,
+            mass_prot: None,
             smile: None,
+            product_ions: None,
+            lc_elution_time: None,
+            lc_elution_comment: None,
         };
 
-        assert!(convert_entry(&entry).is_none());
+        assert_eq!(convert_entry(&entry).unwrap_err(), SkipReason::NonCanonicalParent("X".to_string()));
     }
 }
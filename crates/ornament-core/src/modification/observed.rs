@@ -0,0 +1,509 @@
+//! Modifications observed in MODOMICS tRNA sequences
+//!
+//! MODOMICS writes modified tRNAs in its one-character alphabet ("P" for
+//! pseudouridine, "D" for dihydrouridine, ...). Records are decoded through
+//! the modification resolver into base plus modification, numbered in Sprinzl
+//! coordinates and collected into a set of (organism, isotype, position,
+//! modification) facts.
+//!
+//! Gapped records are read as Sprinzl alignments: either 76 columns (positions
+//! 1-76) or the columns of the standard layout with its insertion positions
+//! (see [`SprinzlMapper::new_standard`]). Gaps mark positions the tRNA lacks,
+//! so CCA-less and indel-carrying records keep their numbering.
+//!
+//! Records are read from JSON (an array, or a map keyed by ID as the API
+//! returns) or FASTA with `>id|organism|isotype|anticodon` headers and an
+//! optional dot-bracket line after the sequence.
+
+use super::context::{ContextPredicate, PairState};
+use super::database::ModificationDatabase;
+use super::resolver::CodeKind;
+use super::sprinzl::{map_structure_arms, SprinzlMapper};
+use super::types::{RnaBase, SprinzlPosition};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// One decoded sequence position
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Residue {
+    Base(RnaBase),
+    /// A modified nucleotide: parent base and modification short name
+    Modified { base: RnaBase, modification: String },
+    /// A character the resolver does not know (e.g. "N")
+    Unknown(char),
+}
+
+impl Residue {
+    /// Unmodified base, if known
+    pub fn base(&self) -> Option<RnaBase> {
+        match self {
+            Residue::Base(base) | Residue::Modified { base, .. } => Some(*base),
+            Residue::Unknown(_) => None,
+        }
+    }
+}
+
+/// A tRNA sequence record in MODOMICS notation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModifiedSequence {
+    pub id: String,
+    pub organism: String,
+    pub isotype: Option<String>,
+    pub anticodon: Option<String>,
+    pub residues: Vec<Residue>,
+    /// Dot-bracket secondary structure, if supplied
+    pub structure: Option<String>,
+    /// Alignment columns with gaps written as '-', if the record had gaps
+    #[serde(default)]
+    pub columns: Option<String>,
+}
+
+impl ModifiedSequence {
+    /// Sequence with modifications written as their parent base ("N" if unknown)
+    pub fn unmodified_sequence(&self) -> String {
+        self.residues.iter().map(|r| r.base().map_or('N', RnaBase::to_char)).collect()
+    }
+
+    /// Sprinzl position -> residue index
+    ///
+    /// Records with a structure are numbered from their arms, gapped records
+    /// from their alignment columns. An ungapped 76 nt record without a
+    /// structure is taken to be in the canonical layout only if its four stems
+    /// pair there (at most one mismatch each), since an insertion and a
+    /// deletion elsewhere also add up to 76 nt. Other records return None.
+    pub fn sprinzl_map(&self) -> Option<HashMap<SprinzlPosition, usize>> {
+        match (&self.structure, &self.columns) {
+            (Some(structure), _) if structure.chars().count() == self.residues.len() => map_structure_arms(structure),
+            (Some(_), _) => None,
+            (None, Some(columns)) => map_columns(columns),
+            (None, None) if self.residues.len() == 76 => {
+                let mapping: HashMap<SprinzlPosition, usize> =
+                    (0..76).map(|i| (SprinzlPosition::from_num(i as u8 + 1), i)).collect();
+                self.stems_pair(&mapping).then_some(mapping)
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Whether the acceptor, D-, anticodon and T-stems pair under a numbering
+    fn stems_pair(&self, mapping: &HashMap<SprinzlPosition, usize>) -> bool {
+        let bases: HashMap<SprinzlPosition, RnaBase> = mapping
+            .iter()
+            .filter_map(|(pos, idx)| Some((pos.clone(), self.residues.get(*idx)?.base()?)))
+            .collect();
+        [1..=7, 10..=13, 27..=31, 49..=53].into_iter().all(|stem| {
+            let mismatches = stem
+                .filter(|n| {
+                    let pair = ContextPredicate::Pair { position: SprinzlPosition::from_num(*n), state: PairState::Paired };
+                    pair.evaluate(&bases) == Some(false)
+                })
+                .count();
+            mismatches <= 1
+        })
+    }
+}
+
+/// Number a gapped record from its Sprinzl alignment columns
+///
+/// 76 columns are positions 1-76; a record as wide as the standard layout is
+/// numbered through it, insertion columns included. Other widths return None.
+fn map_columns(columns: &str) -> Option<HashMap<SprinzlPosition, usize>> {
+    let mapper = SprinzlMapper::new_standard();
+    let width = columns.chars().count();
+    if width == mapper.column_count() {
+        return Some(mapper.map_alignment(columns));
+    }
+    if width != 76 {
+        return None;
+    }
+    let mut mapping = HashMap::new();
+    let mut idx = 0;
+    for (col, c) in columns.chars().enumerate() {
+        if c != '-' {
+            mapping.insert(SprinzlPosition::from_num(col as u8 + 1), idx);
+            idx += 1;
+        }
+    }
+    Some(mapping)
+}
+
+/// An experimentally observed modification at a Sprinzl position
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ObservedModification {
+    pub organism: String,
+    pub isotype: Option<String>,
+    pub position: SprinzlPosition,
+    /// Short name as the database uses it
+    pub modification: String,
+}
+
+/// Modifications observed across a set of sequence records
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ObservedModifications {
+    /// Distinct facts, by organism, isotype, position and modification
+    pub facts: Vec<ObservedModification>,
+    /// Records that could not be numbered in Sprinzl coordinates
+    pub unaligned: Vec<String>,
+    /// Characters that did not resolve to a modification, with their counts
+    pub unknown_codes: Vec<(char, usize)>,
+}
+
+impl ObservedModifications {
+    pub fn from_records(records: &[ModifiedSequence]) -> Self {
+        let mut facts = HashSet::new();
+        let mut unaligned = Vec::new();
+        let mut unknown: HashMap<char, usize> = HashMap::new();
+
+        for record in records {
+            for residue in &record.residues {
+                if let Residue::Unknown(c) = residue {
+                    *unknown.entry(*c).or_default() += 1;
+                }
+            }
+            let Some(mapping) = record.sprinzl_map() else {
+                unaligned.push(record.id.clone());
+                continue;
+            };
+            for (position, idx) in mapping {
+                if let Some(Residue::Modified { modification, .. }) = record.residues.get(idx) {
+                    facts.insert(ObservedModification {
+                        organism: record.organism.clone(),
+                        isotype: record.isotype.clone(),
+                        position,
+                        modification: modification.clone(),
+                    });
+                }
+            }
+        }
+
+        let mapper = SprinzlMapper::new_standard();
+        let mut facts: Vec<ObservedModification> = facts.into_iter().collect();
+        facts.sort_by(|a, b| {
+            (&a.organism, &a.isotype, mapper.sort_key(&a.position), &a.modification)
+                .cmp(&(&b.organism, &b.isotype, mapper.sort_key(&b.position), &b.modification))
+        });
+        let mut unknown_codes: Vec<(char, usize)> = unknown.into_iter().collect();
+        unknown_codes.sort();
+
+        Self { facts, unaligned, unknown_codes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.facts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.facts.is_empty()
+    }
+
+    /// Facts matching every given filter (organism is case-insensitive)
+    pub fn query(
+        &self,
+        organism: Option<&str>,
+        isotype: Option<&str>,
+        position: Option<&SprinzlPosition>,
+    ) -> Vec<&ObservedModification> {
+        self.facts
+            .iter()
+            .filter(|f| organism.is_none_or(|o| f.organism.eq_ignore_ascii_case(o)))
+            .filter(|f| isotype.is_none_or(|i| f.isotype.as_deref() == Some(i)))
+            .filter(|f| position.is_none_or(|p| &f.position == p))
+            .collect()
+    }
+
+    /// Whether a modification was observed (synonyms match through the database)
+    pub fn contains(
+        &self,
+        db: &ModificationDatabase,
+        organism: &str,
+        isotype: Option<&str>,
+        position: &SprinzlPosition,
+        modification: &str,
+    ) -> bool {
+        self.query(Some(organism), isotype, Some(position))
+            .into_iter()
+            .any(|f| db.same_modification(&f.modification, modification))
+    }
+
+    /// Organisms with at least one fact
+    pub fn organisms(&self) -> Vec<&str> {
+        let mut organisms: Vec<&str> = self.facts.iter().map(|f| f.organism.as_str()).collect();
+        organisms.dedup();
+        organisms
+    }
+}
+
+/// Sequence record as MODOMICS serves it
+#[derive(Debug, Deserialize)]
+struct RawRecord {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(default)]
+    organism: Option<String>,
+    #[serde(default, alias = "subtype", alias = "amino_acid")]
+    isotype: Option<String>,
+    #[serde(default)]
+    anticodon: Option<String>,
+    #[serde(alias = "seq")]
+    sequence: String,
+    #[serde(default, alias = "secondary_structure")]
+    structure: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RawRecords {
+    List(Vec<RawRecord>),
+    Map(std::collections::BTreeMap<String, RawRecord>),
+}
+
+/// Check that a JSON body has the shape of a sequence export
+pub fn validate_sequence_json(json: &str) -> Result<usize, SequenceError> {
+    let raw: RawRecords = serde_json::from_str(json).map_err(|e| SequenceError::ParseError(e.to_string()))?;
+    Ok(match raw {
+        RawRecords::List(records) => records.len(),
+        RawRecords::Map(records) => records.len(),
+    })
+}
+
+/// Parse sequence records from JSON
+pub fn parse_sequence_json(json: &str, db: &ModificationDatabase) -> Result<Vec<ModifiedSequence>, SequenceError> {
+    let raw: RawRecords = serde_json::from_str(json).map_err(|e| SequenceError::ParseError(e.to_string()))?;
+    let records: Vec<(String, RawRecord)> = match raw {
+        RawRecords::List(records) => records.into_iter()
+            .enumerate()
+            .map(|(i, r)| (format!("record{}", i + 1), r))
+            .collect(),
+        RawRecords::Map(records) => records.into_iter().collect(),
+    };
+
+    Ok(records
+        .into_iter()
+        .map(|(key, raw)| {
+            let id = match raw.id {
+                Some(serde_json::Value::String(s)) => s,
+                Some(serde_json::Value::Number(n)) => n.to_string(),
+                _ => key,
+            };
+            ModifiedSequence {
+                id,
+                organism: raw.organism.unwrap_or_default(),
+                isotype: raw.isotype.filter(|s| !s.is_empty()),
+                anticodon: raw.anticodon.filter(|s| !s.is_empty()),
+                residues: decode(&raw.sequence, db),
+                structure: raw.structure.filter(|s| !s.is_empty()),
+                columns: gap_columns(&raw.sequence),
+            }
+        })
+        .collect())
+}
+
+/// Parse sequence records from FASTA (`>id|organism|isotype|anticodon`)
+pub fn parse_sequence_fasta(content: &str, db: &ModificationDatabase) -> Result<Vec<ModifiedSequence>, SequenceError> {
+    let mut records = Vec::new();
+    let mut header: Option<&str> = None;
+    let (mut sequence, mut structure) = (String::new(), String::new());
+
+    let mut finish = |header: &str, sequence: &str, structure: &str| {
+        let mut fields = header.split('|').map(str::trim);
+        let field = |s: Option<&str>| s.filter(|s| !s.is_empty()).map(str::to_string);
+        records.push(ModifiedSequence {
+            id: fields.next().unwrap_or_default().to_string(),
+            organism: fields.next().unwrap_or_default().to_string(),
+            isotype: field(fields.next()),
+            anticodon: field(fields.next()),
+            residues: decode(sequence, db),
+            structure: Some(structure.to_string()).filter(|s| !s.is_empty()),
+            columns: gap_columns(sequence),
+        });
+    };
+
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix('>') {
+            if let Some(h) = header {
+                finish(h, &sequence, &structure);
+            }
+            header = Some(rest);
+            sequence.clear();
+            structure.clear();
+        } else if line.is_empty() {
+            continue;
+        } else if header.is_none() {
+            return Err(SequenceError::ParseError(format!("line {}: sequence before the first header", n + 1)));
+        } else if line.chars().all(|c| matches!(c, '(' | ')' | '.' | '<' | '>')) {
+            structure.push_str(line);
+        } else {
+            sequence.push_str(line);
+        }
+    }
+    if let Some(h) = header {
+        finish(h, &sequence, &structure);
+    }
+    Ok(records)
+}
+
+fn is_gap(c: char) -> bool {
+    matches!(c, '-' | '_' | '.' | ' ')
+}
+
+/// Decode a sequence in the MODOMICS alphabet, dropping alignment gaps
+fn decode(sequence: &str, db: &ModificationDatabase) -> Vec<Residue> {
+    sequence.chars().filter(|c| !is_gap(*c)).map(|c| decode_residue(c, db)).collect()
+}
+
+/// Alignment columns of a gapped sequence, every gap character written as '-'
+fn gap_columns(sequence: &str) -> Option<String> {
+    sequence
+        .chars()
+        .any(is_gap)
+        .then(|| sequence.chars().map(|c| if is_gap(c) { '-' } else { c }).collect())
+}
+
+/// Decode one character of the MODOMICS alphabet
+pub fn decode_residue(c: char, db: &ModificationDatabase) -> Residue {
+    match c {
+        'A' => Residue::Base(RnaBase::A),
+        'C' => Residue::Base(RnaBase::C),
+        'G' => Residue::Base(RnaBase::G),
+        'U' => Residue::Base(RnaBase::U),
+        _ => {
+            let code = c.to_string();
+            match db.resolve_as(&code, CodeKind::Unicode).or_else(|_| db.resolve(&code)) {
+                Ok(m) => Residue::Modified { base: m.parent_base, modification: m.short_name.clone() },
+                Err(_) => Residue::Unknown(c),
+            }
+        }
+    }
+}
+
+/// Errors from reading sequence records
+#[derive(Debug)]
+pub enum SequenceError {
+    ParseError(String),
+}
+
+impl fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SequenceError::ParseError(e) => write!(f, "Parse error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Yeast tRNA-Phe with D16, D17, T54 (m5U) and Psi55
+    const PHE: &str = "GCGGAUUUAGCUCAGDDGGGAGAGCGCCAGACUGAAGAUCUGGAGGUCCUGUGTΨCGAUCCACAGAAUUCGCACCA";
+
+    #[test]
+    fn test_decode_and_number() {
+        let db = ModificationDatabase::eukaryotic();
+        let fasta = format!(">phe1|Saccharomyces cerevisiae|Phe|GAA\n{}\n", PHE);
+        let records = parse_sequence_fasta(&fasta, &db).unwrap();
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.isotype.as_deref(), Some("Phe"));
+        assert_eq!(record.residues.len(), 76);
+        assert_eq!(record.unmodified_sequence().len(), 76);
+
+        let observed = ObservedModifications::from_records(&records);
+        let at = |pos: u8| observed.query(None, Some("Phe"), Some(&SprinzlPosition::from_num(pos)))
+            .into_iter()
+            .map(|f| f.modification.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(at(16), ["D"]);
+        assert_eq!(at(17), ["D"]);
+        assert_eq!(at(55), ["Psi"]);
+        assert_eq!(at(54), ["m5U"]);
+        assert!(at(1).is_empty());
+        assert!(observed.contains(&db, "saccharomyces cerevisiae", Some("Phe"), &SprinzlPosition::from_num(55), "Y"));
+    }
+
+    #[test]
+    fn test_gapped_records_keep_numbering() {
+        let db = ModificationDatabase::eukaryotic();
+        let at = |observed: &ObservedModifications, pos: &str| {
+            observed.query(None, None, Some(&SprinzlPosition::new(pos)))
+                .into_iter()
+                .map(|f| f.modification.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // 76 nt, but with 17a inserted and 47 deleted: numbered 1-76 as is,
+        // m7G46 would land at 47, so the ungapped record is left unaligned
+        let variant = format!("{}A{}7{}", &PHE[..17], &PHE[17..45], &PHE[47..]);
+        assert_eq!(variant.chars().count(), 76);
+        let fasta = format!(">phe2|Saccharomyces cerevisiae|Phe|GAA\n{}\n", variant);
+        let observed = ObservedModifications::from_records(&parse_sequence_fasta(&fasta, &db).unwrap());
+        assert!(observed.is_empty());
+        assert_eq!(observed.unaligned, ["phe2"]);
+
+        // The same record in the columns of the standard layout
+        let mapper = SprinzlMapper::new_standard();
+        let mut residues = variant.chars();
+        let columns: String = (0..mapper.column_count())
+            .map(|col| {
+                let label = mapper.get_sprinzl(col).unwrap();
+                let present = label.0 == "17a" || (label.0 != "47" && !label.is_insertion());
+                if present { residues.next().unwrap() } else { '-' }
+            })
+            .collect();
+        assert_eq!(residues.next(), None);
+        let fasta = format!(">phe2|Saccharomyces cerevisiae|Phe|GAA\n{}\n", columns);
+        let records = parse_sequence_fasta(&fasta, &db).unwrap();
+        assert_eq!(records[0].residues.len(), 76);
+        let observed = ObservedModifications::from_records(&records);
+        assert!(observed.unaligned.is_empty());
+        assert_eq!(at(&observed, "46"), ["m7G"]);
+        assert!(at(&observed, "47").is_empty());
+        assert_eq!(at(&observed, "55"), ["Psi"]);
+
+        // CCA-less record with its 3' end gapped in 76 columns
+        let fasta = format!(">phe3|Saccharomyces cerevisiae|Phe|GAA\n{}---\n", &PHE[..PHE.len() - 3]);
+        let records = parse_sequence_fasta(&fasta, &db).unwrap();
+        assert_eq!(records[0].residues.len(), 73);
+        let observed = ObservedModifications::from_records(&records);
+        assert_eq!(at(&observed, "54"), ["m5U"]);
+        assert_eq!(records[0].sprinzl_map().unwrap().len(), 73);
+    }
+
+    #[test]
+    fn test_curated_codes_survive_merge() {
+        // MODOMICS abbreviations replace the curated characters for m1A and Psi
+        let json = r#"{
+            "25": {"id": 25, "name": "1-methyladenosine", "short_name": "m1A", "new_abbrev": "Ѣ", "reference_moiety": ["A"]},
+            "118": {"id": 118, "name": "pseudouridine", "short_name": "Y", "new_abbrev": "P", "reference_moiety": ["U"]}
+        }"#;
+        let merged = ModificationDatabase::from_modomics_json(json).unwrap();
+        let curated = ModificationDatabase::eukaryotic();
+        for code in ['"', 'Ψ'] {
+            assert_eq!(decode_residue(code, &merged), decode_residue(code, &curated));
+        }
+        assert_eq!(decode_residue('"', &merged), Residue::Modified { base: RnaBase::A, modification: "m1A".to_string() });
+        assert_eq!(decode_residue('Ѣ', &merged), decode_residue('"', &merged));
+    }
+
+    #[test]
+    fn test_json_records_and_unaligned() {
+        let db = ModificationDatabase::eukaryotic();
+        let json = r#"{
+            "17": {"id": 17, "organism": "Homo sapiens", "subtype": "Gly", "anticodon": "GCC", "seq": "GCAUUGGUGGUUCAGDGGUAGA"},
+            "18": {"id": 18, "organism": "Homo sapiens", "subtype": "Gly", "seq": "GCA-UUGNN"}
+        }"#;
+        let records = parse_sequence_json(json, &db).unwrap();
+        assert_eq!(records[0].id, "17");
+        assert_eq!(records[1].residues.len(), 8);
+
+        let observed = ObservedModifications::from_records(&records);
+        assert!(observed.is_empty());
+        assert_eq!(observed.unaligned, ["17", "18"]);
+        assert_eq!(observed.unknown_codes, [('N', 2)]);
+        assert_eq!(validate_sequence_json(json).unwrap(), 2);
+    }
+}
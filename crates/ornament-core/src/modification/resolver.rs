@@ -0,0 +1,209 @@
+//! Modification code resolution
+//!
+//! Modifications are written many ways: short names ("m1A", "Psi"), MODOMICS
+//! one-character abbreviations ("P", "\""), ChEBI IDs ("CHEBI:17802") and the
+//! codes modkit writes in bedMethyl files ("a", "m", or a bare ChEBI number).
+//! The index maps every form to the modifications it can mean, tried in
+//! [`CodeKind`] order so a short name always beats a one-character code.
+
+use super::types::{ModCode, Modification};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// modkit's single-letter codes and the modifications they call
+const MODKIT_CODES: &[(&str, &str)] = &[("a", "m6A"), ("m", "m5C"), ("h", "hm5C")];
+
+/// Forms of modification code, in resolution order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum CodeKind {
+    /// Short name or synonym (e.g. "m1A", "Y")
+    ShortName,
+    /// ChEBI ID, with or without the "CHEBI:" prefix
+    ChEBI,
+    /// modkit single-letter code
+    Modkit,
+    /// Single-character code (e.g. "D")
+    SingleChar,
+    /// Unicode or MODOMICS abbreviation character (e.g. "Ψ", "P")
+    Unicode,
+}
+
+impl CodeKind {
+    pub const ALL: [CodeKind; 5] = [
+        CodeKind::ShortName,
+        CodeKind::ChEBI,
+        CodeKind::Modkit,
+        CodeKind::SingleChar,
+        CodeKind::Unicode,
+    ];
+}
+
+impl fmt::Display for CodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodeKind::ShortName => write!(f, "short name"),
+            CodeKind::ChEBI => write!(f, "ChEBI ID"),
+            CodeKind::Modkit => write!(f, "modkit code"),
+            CodeKind::SingleChar => write!(f, "single-character code"),
+            CodeKind::Unicode => write!(f, "MODOMICS abbreviation"),
+        }
+    }
+}
+
+/// Reverse index from codes to short names
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CodeIndex {
+    codes: FxHashMap<(CodeKind, String), Vec<String>>,
+}
+
+impl CodeIndex {
+    /// Index modifications by every code they carry, plus short-name aliases
+    pub fn build(modifications: &FxHashMap<String, Modification>, aliases: &FxHashMap<String, String>) -> Self {
+        let mut index = Self::default();
+
+        for (name, m) in modifications {
+            index.add(CodeKind::ShortName, name.clone(), name);
+            for code in std::iter::once(&m.code).chain(&m.alt_codes) {
+                match code {
+                    ModCode::SingleChar(c) => index.add(CodeKind::SingleChar, c.to_string(), name),
+                    ModCode::Unicode(c) => index.add(CodeKind::Unicode, c.to_string(), name),
+                    ModCode::ChEBI(id) => index.add(CodeKind::ChEBI, id.to_string(), name),
+                    ModCode::ShortName(s) => index.add(CodeKind::ShortName, s.clone(), name),
+                }
+            }
+            if let Some(id) = m.chebi_id {
+                index.add(CodeKind::ChEBI, id.to_string(), name);
+            }
+            if let Some(c) = m.modomics_unicode {
+                index.add(CodeKind::Unicode, c.to_string(), name);
+            }
+        }
+        for (alias, name) in aliases {
+            if modifications.contains_key(name) {
+                index.add(CodeKind::ShortName, alias.clone(), name);
+            }
+        }
+        for (code, name) in MODKIT_CODES {
+            if modifications.contains_key(*name) {
+                index.add(CodeKind::Modkit, code.to_string(), name);
+            }
+        }
+
+        for names in index.codes.values_mut() {
+            names.sort();
+            names.dedup();
+            // MODOMICS gives a 5'-monophosphate ("pY") its nucleoside's abbreviation
+            let nucleosides = names.clone();
+            let canonical = |n: &str| aliases.get(n).cloned().unwrap_or_else(|| n.to_string());
+            names.retain(|n| !n.strip_prefix('p').is_some_and(|base| nucleosides.contains(&canonical(base))));
+        }
+        index
+    }
+
+    fn add(&mut self, kind: CodeKind, code: String, name: &str) {
+        self.codes.entry((kind, code)).or_default().push(name.to_string());
+    }
+
+    /// Short name a code resolves to, trying each kind in order (or only `kind`)
+    pub fn lookup(&self, code: &str, kind: Option<CodeKind>) -> Result<&str, ResolveError> {
+        let code = code.trim();
+        let kinds = match kind {
+            Some(kind) => vec![kind],
+            None => CodeKind::ALL.to_vec(),
+        };
+
+        for kind in kinds {
+            let key = match kind {
+                CodeKind::ChEBI => {
+                    let digits = code.get(..6)
+                        .filter(|prefix| prefix.eq_ignore_ascii_case("chebi:"))
+                        .map_or(code, |_| &code[6..]);
+                    match digits.parse::<u32>() {
+                        Ok(id) => id.to_string(),
+                        Err(_) => continue,
+                    }
+                }
+                _ => code.to_string(),
+            };
+            match self.codes.get(&(kind, key)).map(Vec::as_slice) {
+                Some([name]) => return Ok(name),
+                Some(names) if !names.is_empty() => {
+                    return Err(ResolveError::Ambiguous {
+                        code: code.to_string(),
+                        kind,
+                        candidates: names.to_vec(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Err(ResolveError::Unknown(code.to_string()))
+    }
+}
+
+/// Errors from resolving a modification code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    Unknown(String),
+    /// The code means several modifications
+    Ambiguous { code: String, kind: CodeKind, candidates: Vec<String> },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::Unknown(code) => write!(f, "Unknown modification code '{}'", code),
+            ResolveError::Ambiguous { code, kind, candidates } => write!(
+                f,
+                "Ambiguous modification code '{}' ({}): could be {}",
+                code,
+                kind,
+                candidates.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::ModificationDatabase;
+
+    #[test]
+    fn test_resolve_code_forms() {
+        let db = ModificationDatabase::eukaryotic();
+        for code in ["Psi", "Y", "Ψ", "17802", "CHEBI:17802", "chebi:17802"] {
+            assert_eq!(db.resolve(code).unwrap().short_name, "Psi", "{}", code);
+        }
+        assert_eq!(db.resolve("17596").unwrap().short_name, "I");
+        assert_eq!(db.resolve_as("D", CodeKind::SingleChar).unwrap().short_name, "D");
+        assert_eq!(db.resolve("xyz").unwrap_err(), ResolveError::Unknown("xyz".to_string()));
+    }
+
+    #[test]
+    fn test_modkit_codes_and_ambiguity() {
+        let json = r#"{
+            "96": {"id": 96, "name": "N6-methyladenosine", "short_name": "m6A", "new_abbrev": "Ж", "reference_moiety": ["A"]},
+            "500": {"id": 500, "name": "N6-methyladenosine-5'-monophosphate", "short_name": "pm6A", "new_abbrev": "Ж", "reference_moiety": ["A"]},
+            "601": {"id": 601, "name": "first", "short_name": "xA", "new_abbrev": "ζ", "reference_moiety": ["A"]},
+            "602": {"id": 602, "name": "second", "short_name": "yA", "new_abbrev": "ζ", "reference_moiety": ["A"]}
+        }"#;
+        let db = ModificationDatabase::from_modomics_json(json).unwrap();
+
+        assert_eq!(db.resolve("a").unwrap().short_name, "m6A");
+        // The monophosphate shares its nucleoside's abbreviation
+        assert_eq!(db.resolve("Ж").unwrap().short_name, "m6A");
+
+        let err = db.resolve("ζ").unwrap_err();
+        assert_eq!(err, ResolveError::Ambiguous {
+            code: "ζ".to_string(),
+            kind: CodeKind::Unicode,
+            candidates: vec!["xA".to_string(), "yA".to_string()],
+        });
+        assert!(err.to_string().contains("xA, yA"));
+    }
+}
@@ -0,0 +1,97 @@
+//! Sequencing signatures of modifications
+//!
+//! A modification is invisible in a genomic sequence, but sequences derived
+//! from the RNA itself (tRNA-seq or nanopore consensus) can carry its trace:
+//! inosine reads as G, m1A causes misincorporation or stops reverse
+//! transcription. The records are a bundled table (`data/signatures.toml`)
+//! shared by all domains.
+
+use super::types::RnaBase;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Current version of the signature table schema
+pub const SIGNATURE_SCHEMA_VERSION: u32 = 1;
+
+const BUILTIN: &str = include_str!("../../data/signatures.toml");
+
+/// Qualitative strength of a sequencing artefact
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Propensity {
+    #[default]
+    None,
+    Low,
+    Medium,
+    High,
+}
+
+impl fmt::Display for Propensity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Propensity::None => write!(f, "none"),
+            Propensity::Low => write!(f, "low"),
+            Propensity::Medium => write!(f, "medium"),
+            Propensity::High => write!(f, "high"),
+        }
+    }
+}
+
+/// How a modification shows up in reverse transcription or basecalling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequencingSignature {
+    /// Short name of the modification
+    pub modification: String,
+    /// Bases read in place of the parent base
+    #[serde(default)]
+    pub mismatch_bases: Vec<RnaBase>,
+    /// How often reverse transcription stops at the site
+    #[serde(default)]
+    pub rt_stop: Propensity,
+    /// How often the site is deleted from reads
+    #[serde(default)]
+    pub deletion: Propensity,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl SequencingSignature {
+    /// Whether a read base at the site is this modification's mismatch
+    pub fn reads_as(&self, base: RnaBase) -> bool {
+        self.mismatch_bases.contains(&base)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SignatureTable {
+    schema_version: u32,
+    #[serde(default)]
+    signatures: Vec<SequencingSignature>,
+}
+
+/// The bundled signature records
+pub fn builtin_signatures() -> Vec<SequencingSignature> {
+    let table: SignatureTable = toml::from_str(BUILTIN).expect("bundled signature table is valid TOML");
+    assert!(table.schema_version <= SIGNATURE_SCHEMA_VERSION);
+    table.signatures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_signatures() {
+        let signatures = builtin_signatures();
+        let inosine = signatures.iter().find(|s| s.modification == "I").unwrap();
+        assert!(inosine.reads_as(RnaBase::G));
+        assert!(!inosine.reads_as(RnaBase::A));
+        assert_eq!(inosine.rt_stop, Propensity::None);
+
+        let m1a = signatures.iter().find(|s| s.modification == "m1A").unwrap();
+        assert_eq!(m1a.rt_stop, Propensity::High);
+        for signature in &signatures {
+            assert!(!signature.mismatch_bases.is_empty(), "{}", signature.modification);
+        }
+    }
+}
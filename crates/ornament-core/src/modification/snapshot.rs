@@ -0,0 +1,189 @@
+//! Versioned database snapshots
+//!
+//! A snapshot is a self-contained JSON dump of a [`ModificationDatabase`]:
+//! modifications, aliases, enzymes, sequencing signatures, position
+//! expectations and merge provenance. Its content hash identifies the exact
+//! database an analysis ran against, so it is stamped into analyze and
+//! compare output.
+//!
+//! [`ModificationDatabase`]: super::ModificationDatabase
+
+use super::enzyme::Enzyme;
+use super::expectations::ExpectationRecord;
+use super::merge::{MergeReport, Provenance};
+use super::signature::SequencingSignature;
+use super::taxonomy::Domain;
+use super::types::Modification;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Current version of the snapshot schema
+pub const SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+
+/// A serialized modification database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseSnapshot {
+    pub schema_version: u32,
+    /// "sha256:" followed by the hex digest of the contents
+    pub content_hash: String,
+    #[serde(flatten)]
+    pub contents: SnapshotContents,
+}
+
+/// Everything covered by the content hash
+///
+/// Collections are kept in a fixed order (modifications by short name,
+/// enzymes by ID, signatures by modification, expectations by Sprinzl
+/// position) so equal databases hash equally.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotContents {
+    /// Domain whose expectations the database was built with
+    pub domain: Domain,
+    pub modifications: Vec<Modification>,
+    /// Synonym -> short name
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+    #[serde(default)]
+    pub enzymes: Vec<Enzyme>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<SequencingSignature>,
+    #[serde(default)]
+    pub expectations: Vec<ExpectationRecord>,
+    /// Source of each field, by short name
+    #[serde(default)]
+    pub provenance: BTreeMap<String, Provenance>,
+    /// Present when the database was layered over a MODOMICS snapshot
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_report: Option<MergeReport>,
+}
+
+impl SnapshotContents {
+    /// Hash of the contents' canonical JSON
+    pub fn content_hash(&self) -> String {
+        let json = serde_json::to_vec(self).expect("snapshot contents serialize");
+        let digest = Sha256::digest(&json);
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("sha256:{}", hex)
+    }
+}
+
+impl DatabaseSnapshot {
+    /// Wrap contents with the current schema version and their hash
+    pub fn new(contents: SnapshotContents) -> Self {
+        Self {
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            content_hash: contents.content_hash(),
+            contents,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, SnapshotError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SnapshotError::IoError(format!("{}: {}", path.display(), e)))?;
+        Self::from_json_str(&content)
+    }
+
+    /// Parse a snapshot, checking its version and content hash
+    pub fn from_json_str(s: &str) -> Result<Self, SnapshotError> {
+        let snapshot: Self = serde_json::from_str(s).map_err(|e| SnapshotError::ParseError(e.to_string()))?;
+        if snapshot.schema_version == 0 || snapshot.schema_version > SNAPSHOT_SCHEMA_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(snapshot.schema_version));
+        }
+        let actual = snapshot.contents.content_hash();
+        if actual != snapshot.content_hash {
+            return Err(SnapshotError::HashMismatch {
+                recorded: snapshot.content_hash,
+                actual,
+            });
+        }
+        Ok(snapshot)
+    }
+
+    pub fn to_json_string(&self) -> Result<String, SnapshotError> {
+        serde_json::to_string_pretty(self).map_err(|e| SnapshotError::ParseError(e.to_string()))
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SnapshotError> {
+        std::fs::write(path, self.to_json_string()?)
+            .map_err(|e| SnapshotError::IoError(format!("{}: {}", path.display(), e)))
+    }
+}
+
+/// Errors from reading or loading a snapshot
+#[derive(Debug)]
+pub enum SnapshotError {
+    IoError(String),
+    ParseError(String),
+    UnsupportedVersion(u32),
+    /// The contents were edited after the hash was recorded
+    HashMismatch { recorded: String, actual: String },
+    /// The expectations do not validate against the snapshot's modifications
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::IoError(e) => write!(f, "IO error: {}", e),
+            SnapshotError::ParseError(e) => write!(f, "Parse error: {}", e),
+            SnapshotError::UnsupportedVersion(v) => write!(
+                f,
+                "Unsupported snapshot schema version {} (this build reads up to {})",
+                v, SNAPSHOT_SCHEMA_VERSION
+            ),
+            SnapshotError::HashMismatch { recorded, actual } => write!(
+                f,
+                "Snapshot content hash mismatch: recorded {}, contents hash to {}",
+                recorded, actual
+            ),
+            SnapshotError::Invalid(problems) => {
+                write!(f, "Invalid snapshot:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::modification::ModificationDatabase;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let db = ModificationDatabase::bacterial();
+        let json = db.to_snapshot().to_json_string().unwrap();
+        let loaded = ModificationDatabase::from_snapshot(DatabaseSnapshot::from_json_str(&json).unwrap()).unwrap();
+
+        assert_eq!(loaded.content_hash(), db.content_hash());
+        assert_eq!(loaded.domain(), Domain::Bacterial);
+        assert_eq!(loaded.expectations().len(), db.expectations().len());
+        assert_eq!(loaded.resolve("Y").unwrap().short_name, "Psi");
+        assert!(loaded.find_enzyme("trmA").is_some());
+        assert_ne!(db.content_hash(), ModificationDatabase::eukaryotic().content_hash());
+    }
+
+    #[test]
+    fn test_snapshot_rejects_edits() {
+        let json = ModificationDatabase::eukaryotic().to_snapshot().to_json_string().unwrap();
+        let edited = json.replacen("\"pseudouridine\"", "\"pseudo-uridine\"", 1);
+        assert!(matches!(
+            DatabaseSnapshot::from_json_str(&edited),
+            Err(SnapshotError::HashMismatch { .. })
+        ));
+
+        let future = json.replacen("\"schema_version\": 1", "\"schema_version\": 99", 1);
+        assert!(matches!(
+            DatabaseSnapshot::from_json_str(&future),
+            Err(SnapshotError::UnsupportedVersion(99))
+        ));
+    }
+}
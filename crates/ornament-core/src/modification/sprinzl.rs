@@ -12,6 +12,9 @@ pub struct SprinzlMapper {
     cm_to_sprinzl: HashMap<usize, SprinzlPosition>,
     /// Map from Sprinzl position back to CM column index
     sprinzl_to_cm: HashMap<SprinzlPosition, usize>,
+    /// Number hits from their secondary structure arms instead of CM columns
+    /// (needed for armless mitochondrial tRNAs)
+    structure_aware: bool,
 }
 
 impl SprinzlMapper {
@@ -54,19 +57,52 @@ impl SprinzlMapper {
         Self {
             cm_to_sprinzl,
             sprinzl_to_cm,
+            structure_aware: false,
         }
     }
 
+    /// Create a mapper for mitochondrial tRNAs
+    ///
+    /// Mitochondrial tRNAs can lack the D-arm or T-arm and often have shrunken
+    /// loops, so a fixed column layout misplaces everything downstream of the
+    /// missing arm. This mapper numbers positions from the stems found in the
+    /// hit's secondary structure and only falls back to the standard column
+    /// layout when the structure cannot be parsed.
+    pub fn new_mitochondrial() -> Self {
+        Self {
+            structure_aware: true,
+            ..Self::new_standard()
+        }
+    }
+
+    /// Whether this mapper numbers hits from their secondary structure
+    pub fn is_structure_aware(&self) -> bool {
+        self.structure_aware
+    }
+
     /// Get Sprinzl position for a CM column index
     pub fn get_sprinzl(&self, cm_idx: usize) -> Option<&SprinzlPosition> {
         self.cm_to_sprinzl.get(&cm_idx)
     }
 
+    /// Number of columns in the layout
+    pub fn column_count(&self) -> usize {
+        self.cm_to_sprinzl.len()
+    }
+
     /// Get CM column index for a Sprinzl position
     pub fn get_cm_index(&self, sprinzl: &SprinzlPosition) -> Option<usize> {
         self.sprinzl_to_cm.get(sprinzl).copied()
     }
 
+    /// Sort key placing Sprinzl positions in 5' to 3' order (17 < 17a < 18)
+    ///
+    /// Positions outside the standard layout sort after all known positions.
+    pub fn sort_key(&self, sprinzl: &SprinzlPosition) -> (usize, String) {
+        let idx = self.get_cm_index(sprinzl).unwrap_or(usize::MAX);
+        (idx, sprinzl.0.clone())
+    }
+
     /// Map a sequence alignment to Sprinzl positions
     /// Returns a map from Sprinzl position to the sequence position (0-indexed)
     pub fn map_alignment(&self, alignment: &str) -> HashMap<SprinzlPosition, usize> {
@@ -85,6 +121,20 @@ impl SprinzlMapper {
         result
     }
 
+    /// Map a tRNA hit to Sprinzl positions from its sequence and structure
+    ///
+    /// Structure-aware mappers number the arms found in the dot-bracket
+    /// structure; otherwise (or if the arms cannot be identified) the structure
+    /// is treated as a CM column alignment as in [`Self::map_alignment`].
+    pub fn map_hit(&self, sequence: &str, structure: &str) -> HashMap<SprinzlPosition, usize> {
+        if self.structure_aware && structure.chars().count() == sequence.chars().count() {
+            if let Some(mapping) = map_structure_arms(structure) {
+                return mapping;
+            }
+        }
+        self.map_alignment(structure)
+    }
+
     /// Check if a Sprinzl position is in a functionally important region
     pub fn is_critical_position(pos: &SprinzlPosition) -> bool {
         // Anticodon positions
@@ -103,6 +153,279 @@ impl SprinzlMapper {
     }
 }
 
+/// Numbering layout for one segment of the cloverleaf (a loop, linker or stem strand)
+struct Segment {
+    /// Canonical Sprinzl positions, 5' to 3'
+    slots: &'static [&'static str],
+    /// Named insertion positions, in the order they are used for longer segments
+    insertions: &'static [&'static str],
+    /// Canonical positions dropped first when the segment is shorter than usual
+    deletions: &'static [&'static str],
+    /// Unnumbered extra bases are placed after this many canonical slots
+    open_after: usize,
+}
+
+impl Segment {
+    /// Assign Sprinzl labels to a segment of `len` bases (None = unnumbered)
+    fn number(&self, len: usize) -> Vec<Option<String>> {
+        let mut names: Vec<Option<String>> = self.slots.iter().map(|s| Some(s.to_string())).collect();
+
+        if len < names.len() {
+            for deletion in self.deletions {
+                if names.len() == len {
+                    break;
+                }
+                names.retain(|n| n.as_deref() != Some(*deletion));
+            }
+            names.truncate(len);
+            return names;
+        }
+
+        for insertion in self.insertions {
+            if names.len() >= len {
+                break;
+            }
+            // Insertions go after their base position and any earlier insertions there
+            let base = SprinzlPosition::new(*insertion).base_number();
+            let idx = names
+                .iter()
+                .rposition(|n| {
+                    n.as_deref()
+                        .map(|n| SprinzlPosition::new(n).base_number() == base)
+                        .unwrap_or(false)
+                })
+                .map(|i| i + 1)
+                .unwrap_or(names.len());
+            names.insert(idx, Some(insertion.to_string()));
+        }
+
+        let extra = len.saturating_sub(names.len());
+        let open_at = self.open_after.min(names.len());
+        names.splice(open_at..open_at, vec![None; extra]);
+        names
+    }
+}
+
+const TAIL: Segment = Segment { slots: &["73", "74", "75", "76"], insertions: &[], deletions: &[], open_after: 4 };
+const LINKER_8_9: Segment = Segment { slots: &["8", "9"], insertions: &[], deletions: &["8"], open_after: 1 };
+const LINKER_26: Segment = Segment { slots: &["26"], insertions: &[], deletions: &[], open_after: 0 };
+const VARIABLE: Segment = Segment { slots: &["44", "45", "46", "47", "48"], insertions: &[], deletions: &["47", "45", "44"], open_after: 2 };
+const D_STEM_5P: Segment = Segment { slots: &["10", "11", "12", "13"], insertions: &[], deletions: &["13"], open_after: 4 };
+const D_LOOP: Segment = Segment {
+    slots: &["14", "15", "16", "17", "18", "19", "20", "21"],
+    insertions: &["20a", "17a", "20b"],
+    deletions: &["17", "16", "20"],
+    open_after: 4,
+};
+const AC_STEM_5P: Segment = Segment { slots: &["27", "28", "29", "30", "31"], insertions: &[], deletions: &["27"], open_after: 0 };
+const AC_LOOP: Segment = Segment { slots: &["32", "33", "34", "35", "36", "37", "38"], insertions: &[], deletions: &["38", "32"], open_after: 7 };
+const T_STEM_5P: Segment = Segment { slots: &["49", "50", "51", "52", "53"], insertions: &[], deletions: &["49"], open_after: 0 };
+const T_LOOP: Segment = Segment { slots: &["54", "55", "56", "57", "58", "59", "60"], insertions: &[], deletions: &["60", "59", "57", "56"], open_after: 7 };
+
+/// Sprinzl numbers of paired stem positions add up to a constant (e.g. 1-72, 10-25)
+const ACCEPTOR_PAIR_SUM: u8 = 73;
+const D_PAIR_SUM: u8 = 35;
+const AC_PAIR_SUM: u8 = 70;
+const T_PAIR_SUM: u8 = 114;
+
+/// Canonical stem pairing partner of a Sprinzl position (e.g. 10 -> 25)
+///
+/// Returns None for loop, linker and insertion positions.
+pub fn sprinzl_partner(pos: &SprinzlPosition) -> Option<SprinzlPosition> {
+    if pos.is_insertion() {
+        return None;
+    }
+    let n = pos.base_number()?;
+    let sum = match n {
+        1..=7 | 66..=72 => ACCEPTOR_PAIR_SUM,
+        10..=13 | 22..=25 => D_PAIR_SUM,
+        27..=31 | 39..=43 => AC_PAIR_SUM,
+        49..=53 | 61..=65 => T_PAIR_SUM,
+        _ => return None,
+    };
+    Some(SprinzlPosition::from_num(sum - n))
+}
+
+/// Compute the base-pair partner of every position in a dot-bracket/WUSS string
+pub fn pair_table(structure: &str) -> Vec<Option<usize>> {
+    let chars: Vec<char> = structure.chars().collect();
+    let mut pairs = vec![None; chars.len()];
+    let mut stacks: HashMap<char, Vec<usize>> = HashMap::new();
+
+    for (i, c) in chars.iter().enumerate() {
+        match c {
+            '(' | '<' | '[' | '{' => stacks.entry(*c).or_default().push(i),
+            ')' | '>' | ']' | '}' => {
+                let open = match c {
+                    ')' => '(',
+                    '>' => '<',
+                    ']' => '[',
+                    _ => '{',
+                };
+                if let Some(j) = stacks.get_mut(&open).and_then(|s| s.pop()) {
+                    pairs[i] = Some(j);
+                    pairs[j] = Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pairs
+}
+
+/// Map a tRNA structure to Sprinzl positions by identifying its arms
+///
+/// The acceptor stem is the outermost helix; the hairpins nested inside it are
+/// assigned to the D-, anticodon and T-arms. With only two hairpins the one with
+/// a 7 nt loop is the anticodon arm, which tells whether the D-arm or the T-arm
+/// is missing. Bases in a missing arm's region keep only their linker numbers.
+pub fn map_structure_arms(structure: &str) -> Option<HashMap<SprinzlPosition, usize>> {
+    let pairs = pair_table(structure);
+    let mut labels: Vec<Option<String>> = vec![None; pairs.len()];
+
+    // Acceptor stem: outermost helix, extended while pairs stack
+    let acc_start = pairs.iter().position(|p| p.is_some())?;
+    let acc_partner = pairs[acc_start]?;
+    if acc_partner <= acc_start {
+        return None;
+    }
+    let mut acc_5p_end = acc_start;
+    while acc_5p_end + 1 < pairs.len() && pairs[acc_5p_end + 1] == Some(pairs[acc_5p_end]? - 1) {
+        acc_5p_end += 1;
+    }
+    let acc_3p_start = pairs[acc_5p_end]?;
+    if acc_3p_start <= acc_5p_end {
+        return None;
+    }
+
+    // Acceptor 5' strand is numbered backwards from 7 so short stems keep 7
+    for (k, idx) in (0..=acc_5p_end).rev().take(7).enumerate() {
+        labels[idx] = Some((7 - k).to_string());
+    }
+    label_partners(&mut labels, &pairs, acc_start, acc_5p_end, ACCEPTOR_PAIR_SUM);
+    let tail_start = acc_partner + 1;
+    assign(&mut labels, tail_start, &TAIL.number(pairs.len() - tail_start));
+
+    // Hairpin domains nested inside the acceptor stem
+    let mut domains = Vec::new();
+    let mut k = acc_5p_end + 1;
+    while k < acc_3p_start {
+        match pairs[k] {
+            Some(j) if j > k && j < acc_3p_start => {
+                domains.push((k, j));
+                k = j + 1;
+            }
+            _ => k += 1,
+        }
+    }
+
+    let loop_len = |(start, end): (usize, usize)| -> usize {
+        let (s5, s3) = hairpin_stems(&pairs, start, end);
+        s3.saturating_sub(s5 + 1)
+    };
+
+    let (d_arm, ac_arm, t_arm) = match domains.len() {
+        0 | 1 => return None,
+        2 => {
+            if loop_len(domains[0]) == 7 || loop_len(domains[1]) != 7 {
+                (None, domains[0], Some(domains[1]))
+            } else {
+                (Some(domains[0]), domains[1], None)
+            }
+        }
+        n => (Some(domains[0]), domains[1], Some(domains[n - 1])),
+    };
+
+    // Region between acceptor 5' strand and the anticodon arm
+    let region_start = acc_5p_end + 1;
+    match d_arm {
+        Some((d_start, d_end)) => {
+            assign(&mut labels, region_start, &LINKER_8_9.number(d_start - region_start));
+            number_hairpin(&mut labels, &pairs, (d_start, d_end), &D_STEM_5P, &D_LOOP, D_PAIR_SUM);
+            assign(&mut labels, d_end + 1, &LINKER_26.number(ac_arm.0 - d_end - 1));
+        }
+        None => {
+            // Armless D region: keep 8-9 at the 5' end and 26 next to the anticodon stem
+            let len = ac_arm.0 - region_start;
+            let head = LINKER_8_9.number(len.min(2));
+            assign(&mut labels, region_start, &head);
+            if len > 2 {
+                labels[ac_arm.0 - 1] = Some("26".to_string());
+            }
+        }
+    }
+
+    number_hairpin(&mut labels, &pairs, ac_arm, &AC_STEM_5P, &AC_LOOP, AC_PAIR_SUM);
+
+    match t_arm {
+        Some((t_start, t_end)) => {
+            assign(&mut labels, ac_arm.1 + 1, &VARIABLE.number(t_start - ac_arm.1 - 1));
+            number_hairpin(&mut labels, &pairs, (t_start, t_end), &T_STEM_5P, &T_LOOP, T_PAIR_SUM);
+        }
+        None => {
+            // Armless T region: number the variable region from its 5' end only
+            let len = acc_3p_start - ac_arm.1 - 1;
+            let head = VARIABLE.number(len.min(5));
+            assign(&mut labels, ac_arm.1 + 1, &head);
+        }
+    }
+
+    let mut result = HashMap::new();
+    for (idx, label) in labels.into_iter().enumerate() {
+        if let Some(label) = label {
+            result.insert(SprinzlPosition(label), idx);
+        }
+    }
+    Some(result)
+}
+
+/// Find the innermost paired positions of a hairpin's stem (5' and 3' side)
+fn hairpin_stems(pairs: &[Option<usize>], start: usize, end: usize) -> (usize, usize) {
+    let (mut i, mut j) = (start, end);
+    while i + 1 < j && pairs[i + 1] == Some(j - 1) {
+        i += 1;
+        j -= 1;
+    }
+    (i, j)
+}
+
+/// Number a stem-loop domain spanning `start..=end`
+fn number_hairpin(
+    labels: &mut [Option<String>],
+    pairs: &[Option<usize>],
+    (start, end): (usize, usize),
+    stem_5p: &Segment,
+    loop_segment: &Segment,
+    pair_sum: u8,
+) {
+    let (inner_5p, inner_3p) = hairpin_stems(pairs, start, end);
+    assign(labels, start, &stem_5p.number(inner_5p - start + 1));
+    assign(labels, inner_5p + 1, &loop_segment.number(inner_3p - inner_5p - 1));
+    label_partners(labels, pairs, start, inner_5p, pair_sum);
+}
+
+/// Label the 3' strand of a stem from the 5' strand labels of its partners
+fn label_partners(labels: &mut [Option<String>], pairs: &[Option<usize>], from: usize, to: usize, pair_sum: u8) {
+    for i in from..=to {
+        let partner_label = labels[i]
+            .as_deref()
+            .and_then(|l| l.parse::<u8>().ok())
+            .map(|n| (pair_sum - n).to_string());
+        if let (Some(j), Some(label)) = (pairs[i], partner_label) {
+            labels[j] = Some(label);
+        }
+    }
+}
+
+fn assign(labels: &mut [Option<String>], start: usize, segment: &[Option<String>]) {
+    for (k, label) in segment.iter().enumerate() {
+        if let Some(slot) = labels.get_mut(start + k) {
+            *slot = label.clone();
+        }
+    }
+}
+
 impl Default for SprinzlMapper {
     fn default() -> Self {
         Self::new_standard()
@@ -132,4 +455,39 @@ mod tests {
         assert!(SprinzlMapper::is_critical_position(&SprinzlPosition("55".to_string())));
         assert!(!SprinzlMapper::is_critical_position(&SprinzlPosition("1".to_string())));
     }
+
+    #[test]
+    fn test_map_structure_arms_cloverleaf() {
+        let structure = "(((((((..((((.........)))).(((((.......))))).....(((((.......))))))))))))....";
+        let mapping = map_structure_arms(structure).unwrap();
+
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(1)), Some(&0));
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(34)), Some(&34));
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(55)), Some(&55));
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(72)), Some(&72));
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(76)), Some(&76));
+    }
+
+    #[test]
+    fn test_map_structure_arms_without_d_arm() {
+        // mt-tRNA-Ser(AGY)-like: acceptor stem, D-arm replaced by a short loop
+        let structure = "(((((((.....(((((.......))))).....(((((.......))))))))))))....";
+        let mapping = map_structure_arms(structure).unwrap();
+
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(8)), Some(&7));
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(26)), Some(&11));
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(34)), Some(&19));
+        assert!(!mapping.contains_key(&SprinzlPosition::from_num(16)));
+        assert_eq!(mapping.get(&SprinzlPosition::from_num(55)), Some(&40));
+    }
+
+    #[test]
+    fn test_mitochondrial_mapper_falls_back() {
+        let mapper = SprinzlMapper::new_mitochondrial();
+        assert!(mapper.is_structure_aware());
+
+        // Unparseable structure falls back to column mapping
+        let mapping = mapper.map_hit("ACGU", "....");
+        assert!(mapping.is_empty());
+    }
 }
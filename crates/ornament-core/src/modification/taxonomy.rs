@@ -0,0 +1,195 @@
+//! Domains of life and taxonomy lookup
+//!
+//! Selects which expectation set applies to a genome, either by name or by
+//! NCBI taxonomy ID resolved through a bundled lineage table.
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Bundled lineage table (taxid, nearest listed ancestor, name)
+const LINEAGE_TABLE: &str = include_str!("../../data/taxonomy_lineages.tsv");
+
+const TAXID_BACTERIA: u32 = 2;
+const TAXID_ARCHAEA: u32 = 2157;
+const TAXID_EUKARYOTA: u32 = 2759;
+
+/// Domain (or organelle) whose tRNA modification expectations apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Domain {
+    /// Eukaryotic cytosolic tRNAs
+    #[default]
+    Eukaryotic,
+    Bacterial,
+    Archaeal,
+    /// Chloroplast/plastid tRNAs
+    Plastid,
+    /// Mitochondrial tRNAs
+    Mitochondrial,
+}
+
+impl Domain {
+    pub const ALL: [Domain; 5] = [
+        Domain::Eukaryotic,
+        Domain::Bacterial,
+        Domain::Archaeal,
+        Domain::Plastid,
+        Domain::Mitochondrial,
+    ];
+
+    /// Canonical lowercase name
+    pub fn name(self) -> &'static str {
+        match self {
+            Domain::Eukaryotic => "eukaryotic",
+            Domain::Bacterial => "bacterial",
+            Domain::Archaeal => "archaeal",
+            Domain::Plastid => "plastid",
+            Domain::Mitochondrial => "mitochondrial",
+        }
+    }
+
+    /// Parse a domain name (e.g. "bacteria", "archaeal", "chloroplast", "mito")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "eukaryotic" | "eukaryota" | "eukaryote" | "euk" | "nuclear" | "cytosolic" => {
+                Some(Domain::Eukaryotic)
+            }
+            "bacterial" | "bacteria" | "bact" => Some(Domain::Bacterial),
+            "archaeal" | "archaea" | "arch" => Some(Domain::Archaeal),
+            "plastid" | "chloroplast" | "plastidial" => Some(Domain::Plastid),
+            "mitochondrial" | "mitochondrion" | "mito" | "mt" => Some(Domain::Mitochondrial),
+            _ => None,
+        }
+    }
+
+    /// Resolve the domain of an NCBI taxonomy ID through the bundled lineage table
+    ///
+    /// Organelles are not taxa, so this only ever returns Eukaryotic, Bacterial or Archaeal.
+    pub fn from_taxid(taxid: u32) -> Option<Self> {
+        let table = lineage_table();
+        let mut current = taxid;
+
+        // The table is acyclic, but guard against a malformed edit looping forever
+        for _ in 0..table.len() + 1 {
+            match current {
+                TAXID_BACTERIA => return Some(Domain::Bacterial),
+                TAXID_ARCHAEA => return Some(Domain::Archaeal),
+                TAXID_EUKARYOTA => return Some(Domain::Eukaryotic),
+                _ => {}
+            }
+            current = table.get(&current)?.0;
+        }
+        None
+    }
+}
+
+impl Domain {
+    /// Resolve the domain of an organism name through the bundled lineage table
+    ///
+    /// Tries the full scientific name, then the genus (e.g. "Escherichia" for
+    /// an unlisted strain). Case-insensitive.
+    pub fn from_organism(organism: &str) -> Option<Self> {
+        let table = lineage_table();
+        let lookup = |name: &str| {
+            table.iter()
+                .find(|(_, (_, listed))| listed.eq_ignore_ascii_case(name))
+                .map(|(taxid, _)| *taxid)
+        };
+        let organism = organism.trim();
+        let genus = organism.split_whitespace().next()?;
+        let taxid = lookup(organism).or_else(|| lookup(genus))?;
+        Self::from_taxid(taxid)
+    }
+}
+
+impl fmt::Display for Domain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Domain {
+    type Err = DomainError;
+
+    /// Parse either a domain name or a numeric NCBI taxonomy ID
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Ok(taxid) = s.parse::<u32>() {
+            return Domain::from_taxid(taxid).ok_or(DomainError::UnknownTaxid(taxid));
+        }
+        Domain::from_name(s).ok_or_else(|| DomainError::UnknownName(s.to_string()))
+    }
+}
+
+/// Look up the scientific name of a taxid in the bundled lineage table
+pub fn taxon_name(taxid: u32) -> Option<&'static str> {
+    lineage_table().get(&taxid).map(|(_, name)| *name)
+}
+
+fn lineage_table() -> FxHashMap<u32, (u32, &'static str)> {
+    let mut table = FxHashMap::default();
+    for line in LINEAGE_TABLE.lines() {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 3 {
+            continue;
+        }
+        if let (Ok(taxid), Ok(ancestor)) = (fields[0].parse::<u32>(), fields[1].parse::<u32>()) {
+            table.insert(taxid, (ancestor, fields[2]));
+        }
+    }
+    table
+}
+
+/// Errors from resolving a domain
+#[derive(Debug)]
+pub enum DomainError {
+    UnknownName(String),
+    UnknownTaxid(u32),
+}
+
+impl fmt::Display for DomainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DomainError::UnknownName(name) => write!(
+                f,
+                "Unknown domain '{}' (expected one of: eukaryotic, bacterial, archaeal, plastid, mitochondrial, or an NCBI taxid)",
+                name
+            ),
+            DomainError::UnknownTaxid(taxid) => {
+                write!(f, "Taxonomy ID {} is not in the bundled lineage table", taxid)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DomainError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_from_name() {
+        assert_eq!("Bacteria".parse::<Domain>().unwrap(), Domain::Bacterial);
+        assert_eq!("chloroplast".parse::<Domain>().unwrap(), Domain::Plastid);
+        assert_eq!("mito".parse::<Domain>().unwrap(), Domain::Mitochondrial);
+        assert!("plantae".parse::<Domain>().is_err());
+    }
+
+    #[test]
+    fn test_domain_from_taxid() {
+        assert_eq!(Domain::from_taxid(511145), Some(Domain::Bacterial));
+        assert_eq!(Domain::from_taxid(2261), Some(Domain::Archaeal));
+        assert_eq!(Domain::from_taxid(9606), Some(Domain::Eukaryotic));
+        assert_eq!(Domain::from_taxid(1), None);
+        assert!(matches!("999999999".parse::<Domain>(), Err(DomainError::UnknownTaxid(_))));
+        assert_eq!(taxon_name(4932), Some("Saccharomyces cerevisiae"));
+        assert_eq!(Domain::from_organism("saccharomyces cerevisiae"), Some(Domain::Eukaryotic));
+        assert_eq!(Domain::from_organism("Escherichia coli BL21"), Some(Domain::Bacterial));
+        assert_eq!(Domain::from_organism("Unknownia obscura"), None);
+    }
+}
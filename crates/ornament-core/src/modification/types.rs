@@ -1,6 +1,8 @@
 //! Core types for RNA modifications
 
+use super::context::ContextPredicate;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 
 /// RNA nucleotide bases
@@ -61,6 +63,126 @@ impl fmt::Display for RnaBase {
     }
 }
 
+/// A sequenced base that may be an IUPAC ambiguity code (R, Y, N, ...)
+///
+/// Holds the set of bases the code allows; soft-masked (lowercase) bases are
+/// read like uppercase ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct IupacBase(u8);
+
+impl IupacBase {
+    const A: u8 = 1;
+    const C: u8 = 2;
+    const G: u8 = 4;
+    const U: u8 = 8;
+
+    /// Parse an IUPAC nucleotide code (T is read as U); gaps and other symbols give None
+    pub fn from_char(c: char) -> Option<Self> {
+        let bits = match c.to_ascii_uppercase() {
+            'A' => Self::A,
+            'C' => Self::C,
+            'G' => Self::G,
+            'T' | 'U' => Self::U,
+            'R' => Self::A | Self::G,
+            'Y' => Self::C | Self::U,
+            'S' => Self::C | Self::G,
+            'W' => Self::A | Self::U,
+            'K' => Self::G | Self::U,
+            'M' => Self::A | Self::C,
+            'B' => Self::C | Self::G | Self::U,
+            'D' => Self::A | Self::G | Self::U,
+            'H' => Self::A | Self::C | Self::U,
+            'V' => Self::A | Self::C | Self::G,
+            'N' => Self::A | Self::C | Self::G | Self::U,
+            _ => return None,
+        };
+        Some(Self(bits))
+    }
+
+    fn bit(base: RnaBase) -> u8 {
+        match base {
+            RnaBase::A => Self::A,
+            RnaBase::C => Self::C,
+            RnaBase::G => Self::G,
+            RnaBase::U => Self::U,
+        }
+    }
+
+    /// Whether the code allows a base
+    pub fn contains(self, base: RnaBase) -> bool {
+        self.0 & Self::bit(base) != 0
+    }
+
+    /// The bases the code allows, in A, C, G, U order
+    pub fn bases(self) -> Vec<RnaBase> {
+        [RnaBase::A, RnaBase::C, RnaBase::G, RnaBase::U]
+            .into_iter()
+            .filter(|b| self.contains(*b))
+            .collect()
+    }
+
+    /// The base, if the code allows exactly one
+    pub fn definite(self) -> Option<RnaBase> {
+        match self.bases().as_slice() {
+            [base] => Some(*base),
+            _ => None,
+        }
+    }
+
+    /// N: any base, so nothing about the site is known
+    pub fn is_unknown(self) -> bool {
+        self.0 == Self::A | Self::C | Self::G | Self::U
+    }
+
+    /// The uppercase RNA IUPAC code
+    pub fn to_char(self) -> char {
+        "?ACMGRSVUWYHKDBN".as_bytes()[self.0 as usize] as char
+    }
+}
+
+impl From<RnaBase> for IupacBase {
+    fn from(base: RnaBase) -> Self {
+        Self(Self::bit(base))
+    }
+}
+
+impl From<IupacBase> for String {
+    fn from(base: IupacBase) -> Self {
+        base.to_char().to_string()
+    }
+}
+
+impl TryFrom<String> for IupacBase {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let mut chars = s.chars();
+        match (chars.next().and_then(Self::from_char), chars.next()) {
+            (Some(base), None) => Ok(base),
+            _ => Err(format!("'{}' is not an IUPAC nucleotide code", s)),
+        }
+    }
+}
+
+impl fmt::Display for IupacBase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_char())
+    }
+}
+
+/// Whether a possibly ambiguous base can carry a modification
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BaseCompatibility {
+    /// Every base the code allows can carry it
+    Compatible,
+    /// Some of the bases can
+    Partial,
+    /// None of them can
+    Incompatible,
+}
+
 /// Modification code representation
 /// Supports multiple naming conventions from MODOMICS
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -110,6 +232,34 @@ pub struct Modification {
     pub chebi_id: Option<u32>,
     /// MODOMICS unicode character if available
     pub modomics_unicode: Option<char>,
+    /// MODOMICS database ID
+    #[serde(default)]
+    pub modomics_id: Option<u32>,
+    /// Formula, masses and LC-MS properties, if known
+    #[serde(default)]
+    pub chemistry: Option<ChemicalProperties>,
+}
+
+/// Chemical properties of a modified nucleoside, as recorded by MODOMICS
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChemicalProperties {
+    /// Molecular formula (e.g. "C9H12N2O6")
+    pub formula: Option<String>,
+    /// Average mass (Da)
+    pub mass_avg: Option<f64>,
+    /// Monoisotopic mass (Da)
+    pub mass_monoiso: Option<f64>,
+    /// Monoisotopic mass of the protonated ion, [M+H]+ (Da)
+    pub mass_prot: Option<f64>,
+    /// Structure as SMILES
+    pub smiles: Option<String>,
+    /// Characteristic MS/MS product ions (m/z)
+    #[serde(default)]
+    pub product_ions: Vec<f64>,
+    /// LC retention time, as reported (free text with sources)
+    pub lc_elution_time: Option<String>,
+    /// LC elution order relative to the canonical nucleosides
+    pub lc_elution_comment: Option<String>,
 }
 
 impl Modification {
@@ -118,6 +268,19 @@ impl Modification {
         !self.incompatible_bases.contains(&observed)
     }
 
+    /// Compatibility with a possibly ambiguous observed base
+    pub fn compatibility(&self, observed: IupacBase) -> BaseCompatibility {
+        let bases = observed.bases();
+        let compatible = bases.iter().filter(|b| self.is_compatible(**b)).count();
+        if compatible == bases.len() {
+            BaseCompatibility::Compatible
+        } else if compatible == 0 {
+            BaseCompatibility::Incompatible
+        } else {
+            BaseCompatibility::Partial
+        }
+    }
+
     /// Check if an observed base is the expected genomic base
     pub fn is_expected(&self, observed: RnaBase) -> bool {
         observed == self.genomic_expectation
@@ -212,6 +375,153 @@ pub struct PositionModExpectation {
     /// Which isotypes this applies to (empty = all)
     #[serde(default)]
     pub isotypes: Vec<String>,
+    /// Free-text curator notes (enzyme, literature, caveats)
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Sequence context selecting the tRNAs this applies to (e.g. U36 for t6A37)
+    #[serde(default)]
+    pub applies_when: Vec<ContextPredicate>,
+    /// Sequence context the modifying enzyme needs; losing it is an incompatibility
+    #[serde(default)]
+    pub requires: Vec<ContextPredicate>,
+    /// IDs of the enzymes that install these modifications
+    #[serde(default)]
+    pub enzymes: Vec<String>,
+    /// Modifications elsewhere that must be present first
+    #[serde(default)]
+    pub depends_on: Vec<Prerequisite>,
+    /// Literature support
+    #[serde(default)]
+    pub evidence: Option<Evidence>,
+    /// Fraction (0-1) of reference tRNAs carrying the modification, when the
+    /// expectation was learned from data; sets severity in place of `conservation`
+    #[serde(default)]
+    pub frequency: Option<f64>,
+    /// Set when the site is edited rather than (only) modified
+    #[serde(default)]
+    pub editing: Option<Editing>,
+}
+
+/// An RNA editing event, as the bases read in cDNA
+///
+/// A-to-I editing reads as A to G: inosine pairs like G.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Editing {
+    /// Base encoded in the genome
+    pub from: RnaBase,
+    /// Base read once the site is edited
+    pub to: RnaBase,
+}
+
+impl fmt::Display for Editing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-to-{}", self.from, self.to)
+    }
+}
+
+/// How an expected modification was established
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceCode {
+    /// Read directly from sequenced tRNA
+    DirectSequencing,
+    /// Detected by mass spectrometry of tRNA digests
+    MassSpectrometry,
+    /// Carried over from related organisms or from the enzyme's presence
+    InferredByHomology,
+    /// Listed for a clade by the table's curators, without a checked primary citation
+    Curated,
+}
+
+impl fmt::Display for EvidenceCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvidenceCode::DirectSequencing => write!(f, "direct sequencing"),
+            EvidenceCode::MassSpectrometry => write!(f, "mass spectrometry"),
+            EvidenceCode::InferredByHomology => write!(f, "inferred by homology"),
+            EvidenceCode::Curated => write!(f, "curated"),
+        }
+    }
+}
+
+/// Literature support for an expectation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Evidence {
+    pub code: EvidenceCode,
+    /// "doi:10.…" or "PMID:…" references
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Organisms or clades the observations come from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub organisms: Vec<String>,
+}
+
+impl Evidence {
+    /// Describe the first malformed reference, if any
+    pub fn problem(&self) -> Option<String> {
+        self.references.iter().find(|r| !is_reference(r)).map(|r| {
+            format!("reference '{}' is neither doi:10.<prefix>/<suffix> nor PMID:<digits>", r)
+        })
+    }
+}
+
+impl fmt::Display for Evidence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        if !self.organisms.is_empty() {
+            write!(f, " in {}", self.organisms.join(", "))?;
+        }
+        if !self.references.is_empty() {
+            write!(f, " [{}]", self.references.join("; "))?;
+        }
+        Ok(())
+    }
+}
+
+fn is_reference(reference: &str) -> bool {
+    if let Some(doi) = reference.strip_prefix("doi:") {
+        return doi.starts_with("10.") && doi.split_once('/').is_some_and(|(_, suffix)| !suffix.is_empty());
+    }
+    reference
+        .strip_prefix("PMID:")
+        .is_some_and(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// A modification at another site that an expectation depends on (e.g. t6A37 for ct6A37)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Prerequisite {
+    pub position: SprinzlPosition,
+    /// Short name of the prerequisite modification
+    pub modification: String,
+}
+
+impl fmt::Display for Prerequisite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.modification, self.position)
+    }
+}
+
+impl PositionModExpectation {
+    /// Whether the context selecting this expectation holds
+    ///
+    /// Undecidable predicates (unmapped or ambiguous positions) do not select it.
+    pub fn applies_to(&self, bases: &HashMap<SprinzlPosition, RnaBase>) -> bool {
+        self.applies_when.iter().all(|p| p.evaluate(bases) == Some(true))
+    }
+
+    /// The first required context a tRNA has lost, if any
+    pub fn lost_context(&self, bases: &HashMap<SprinzlPosition, RnaBase>) -> Option<&ContextPredicate> {
+        self.requires.iter().find(|p| p.evaluate(bases) == Some(false))
+    }
+
+    /// Names the expectation is reported under: its modifications, or the
+    /// editing event when it installs none (e.g. "C-to-U")
+    pub fn names(&self) -> Vec<String> {
+        match self.editing {
+            Some(editing) if self.modifications.is_empty() => vec![editing.to_string()],
+            _ => self.modifications.iter().map(|m| m.short_name.clone()).collect(),
+        }
+    }
 }
 
 /// Strand orientation
@@ -278,6 +588,13 @@ impl Isotype {
     pub const SUP: &'static str = "Sup"; // Suppressor
     pub const IMET: &'static str = "iMet"; // Initiator methionine
     pub const UNDET: &'static str = "Undet"; // Undetermined
+
+    /// All isotype names recognized in expectation tables
+    pub const ALL: [&'static str; 24] = [
+        Self::ALA, Self::ARG, Self::ASN, Self::ASP, Self::CYS, Self::GLN, Self::GLU, Self::GLY,
+        Self::HIS, Self::ILE, Self::LEU, Self::LYS, Self::MET, Self::PHE, Self::PRO, Self::SER,
+        Self::THR, Self::TRP, Self::TYR, Self::VAL, Self::SEC, Self::SUP, Self::IMET, Self::UNDET,
+    ];
 }
 
 impl fmt::Display for Isotype {
@@ -309,6 +626,23 @@ mod tests {
         assert!(pos_ins.is_insertion());
     }
 
+    #[test]
+    fn test_evidence_references() {
+        let mut evidence = Evidence {
+            code: EvidenceCode::DirectSequencing,
+            references: vec!["doi:10.1093/nar/gkab1083".to_string(), "PMID:123".to_string()],
+            organisms: vec!["Escherichia coli".to_string()],
+        };
+        assert_eq!(evidence.problem(), None);
+        assert_eq!(
+            evidence.to_string(),
+            "direct sequencing in Escherichia coli [doi:10.1093/nar/gkab1083; PMID:123]"
+        );
+
+        evidence.references.push("Sprinzl 1998".to_string());
+        assert!(evidence.problem().unwrap().contains("Sprinzl 1998"));
+    }
+
     #[test]
     fn test_modification_compatibility() {
         let psi = Modification {
@@ -321,6 +655,8 @@ mod tests {
             incompatible_bases: vec![RnaBase::A, RnaBase::G, RnaBase::C],
             chebi_id: Some(17802),
             modomics_unicode: Some('Ψ'),
+            modomics_id: None,
+            chemistry: None,
         };
 
         assert!(psi.is_compatible(RnaBase::U));
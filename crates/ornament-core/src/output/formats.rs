@@ -1,6 +1,7 @@
 //! Output format implementations
 
-use crate::analysis::ModCompatibilityResult;
+use crate::analysis::per_position::position_rows;
+use crate::analysis::{ModCompatibilityResult, Strand};
 
 /// Convert results to JSON
 pub fn to_json(results: &[ModCompatibilityResult]) -> serde_json::Result<String> {
@@ -12,12 +13,12 @@ pub fn to_tsv(results: &[ModCompatibilityResult]) -> String {
     let mut output = String::new();
 
     // Header
-    output.push_str("id\tseq_name\tstart\tend\tstrand\tscore\tisotype\tanticodon\tis_odd\tcompatibility_score\n");
+    output.push_str("id\tseq_name\tstart\tend\tstrand\tscore\tisotype\tanticodon\tis_odd\tcompatibility_score\tlog_likelihood\tnot_evaluable\n");
 
     for result in results {
         let hit = &result.hit;
         output.push_str(&format!(
-            "{}\t{}\t{}\t{}\t{:?}\t{:.2}\t{}\t{}\t{}\t{:.3}\n",
+            "{}\t{}\t{}\t{}\t{:?}\t{:.2}\t{}\t{}\t{}\t{:.3}\t{:.3}\t{}\n",
             hit.id,
             hit.seq_name,
             hit.start,
@@ -28,8 +29,78 @@ pub fn to_tsv(results: &[ModCompatibilityResult]) -> String {
             hit.anticodon.as_deref().unwrap_or("-"),
             result.is_odd,
             result.compatibility_score,
+            result.log_likelihood,
+            result.not_evaluable,
         ));
     }
 
     output
 }
+
+/// Columns of the per-position table, in output order
+pub const PER_POSITION_COLUMNS: [&str; 13] = [
+    "trna_id",
+    "seq_name",
+    "strand",
+    "position",
+    "seq_index",
+    "genomic_position",
+    "observed_base",
+    "observed_modification",
+    "expected_modifications",
+    "verdict",
+    "severity",
+    "mapping_confidence",
+    "is_odd",
+];
+
+/// Long-format TSV: one row per (tRNA, Sprinzl position), tRNAs in input order
+///
+/// Missing values are written as `.`; expected modifications are comma-separated.
+pub fn to_per_position_tsv(results: &[ModCompatibilityResult]) -> String {
+    let mut output = PER_POSITION_COLUMNS.join("\t");
+    output.push('\n');
+
+    for result in results {
+        for row in position_rows(result) {
+            let fields = [
+                row.trna_id,
+                row.seq_name,
+                match row.strand {
+                    Strand::Plus => "+".to_string(),
+                    Strand::Minus => "-".to_string(),
+                },
+                row.position.0,
+                row.seq_index.to_string(),
+                row.genomic_position.map_or_else(|| ".".to_string(), |p| p.to_string()),
+                row.observed_base.to_string(),
+                row.observed_modification.unwrap_or_else(|| ".".to_string()),
+                if row.expected_modifications.is_empty() {
+                    ".".to_string()
+                } else {
+                    row.expected_modifications.join(",")
+                },
+                row.verdict.map_or_else(|| ".".to_string(), |v| v.to_string()),
+                row.severity.map_or_else(|| ".".to_string(), |s| format!("{:?}", s)),
+                row.mapping_confidence.to_string(),
+                row.is_odd.to_string(),
+            ];
+            output.push_str(&fields.join("\t"));
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+/// Long-format JSON Lines: one object per (tRNA, Sprinzl position)
+pub fn to_per_position_jsonl(results: &[ModCompatibilityResult]) -> serde_json::Result<String> {
+    let mut output = String::new();
+    for result in results {
+        for row in position_rows(result) {
+            output.push_str(&serde_json::to_string(&row)?);
+            output.push('\n');
+        }
+    }
+    Ok(output)
+}
@@ -2,4 +2,4 @@
 
 pub mod formats;
 
-pub use formats::{to_json, to_tsv};
+pub use formats::{to_json, to_per_position_jsonl, to_per_position_tsv, to_tsv, PER_POSITION_COLUMNS};
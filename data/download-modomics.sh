@@ -1,5 +1,6 @@
 #!/bin/bash
 # Download MODOMICS modification data
+# (`ornament db update` does the same in Rust, with an ETag-validated cache)
 # Run from the data/ directory or provide output path
 
 set -e
@@ -44,6 +45,7 @@ echo "  - smile: Chemical structure"
 echo "  - new_abbrev: MODOMICS unicode character"
 echo ""
 echo "Note: Position-specific expectations (which modifications at which"
-echo "tRNA positions) are currently hardcoded in the Rust database based"
-echo "on literature. To update those, edit:"
-echo "  crates/ornament-core/src/modification/database.rs"
+echo "tRNA positions) are kept in versioned TOML files, one per domain:"
+echo "  crates/ornament-core/data/expectations/*.toml"
+echo "Export a table with 'ornament mods --export-expectations FILE' and load"
+echo "an edited copy with '--expectations FILE' (no rebuild needed)."